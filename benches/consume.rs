@@ -0,0 +1,58 @@
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use scanny::Scanny;
+
+fn words(sc: &Scanny) -> usize {
+    let mut count = 0;
+    while sc.peek().is_some() {
+        sc.skeep_while(char::is_whitespace)
+            .matcher()
+            .consume_while(|v| !v.is_whitespace())
+            .finalize(|_| ());
+        count += 1;
+    }
+    count
+}
+
+fn bench_consume_while(c: &mut Criterion) {
+    let ascii = "let foo_bar = baz(1234, \"qux\");\n".repeat(10_000);
+    // a single non-ASCII char disables the ASCII fast path
+    let unicode = format!("{ascii}é");
+    c.bench_function("consume_while ascii", |b| {
+        b.iter(|| words(&Scanny::new(black_box(&ascii))))
+    });
+    c.bench_function("consume_while unicode", |b| {
+        b.iter(|| words(&Scanny::new(black_box(&unicode))))
+    });
+    let hex = "0123456789abcdefABCDEF ".repeat(10_000);
+    let ranges = ['0'..='9', 'a'..='f', 'A'..='F'];
+    c.bench_function("consume_while_in hex", |b| {
+        b.iter(|| {
+            let sc = Scanny::new(black_box(&hex));
+            while sc.peek().is_some() {
+                sc.consume_while_in(&ranges);
+                sc.bump();
+            }
+        })
+    });
+    c.bench_function("consume_while hex closure", |b| {
+        b.iter(|| {
+            let sc = Scanny::new(black_box(&hex));
+            while sc.peek().is_some() {
+                sc.consume_while(char::is_ascii_hexdigit);
+                sc.bump();
+            }
+        })
+    });
+    c.bench_function("consume_until_any ascii", |b| {
+        b.iter(|| {
+            let sc = Scanny::new(black_box(&ascii));
+            while sc.peek().is_some() {
+                sc.consume_until_any(&['\n']);
+                sc.bump();
+            }
+        })
+    });
+}
+
+criterion_group!(benches, bench_consume_while);
+criterion_main!(benches);
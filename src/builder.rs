@@ -0,0 +1,148 @@
+use crate::scanner::Scanny;
+
+/// Options of a [Scanny], set before scanning starts.
+///
+/// Created with [`Scanny::builder`], [`Scanny::new`] uses the defaults.
+/// # Example
+/// ```rust
+/// use scanny::Scanny;
+///
+/// let sc = Scanny::builder("\tx\ny")
+///     .tab_width(4)
+///     .zero_based_lines()
+///     .max_token_bytes(64)
+///     .build();
+/// sc.bump();
+/// assert_eq!((sc.position().line, sc.position().column), (0, 5));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ScannyBuilder<'a> {
+    value: &'a str,
+    tab_width: usize,
+    line_base: usize,
+    crlf: bool,
+    max_token_bytes: usize,
+    ascii_fast_path: bool,
+    number_format: (char, char),
+}
+
+impl<'a> ScannyBuilder<'a> {
+    pub fn new(value: &'a str) -> Self {
+        Self {
+            value,
+            tab_width: 1,
+            line_base: 1,
+            crlf: true,
+            max_token_bytes: usize::MAX,
+            ascii_fast_path: true,
+            number_format: ('.', '_'),
+        }
+    }
+    /// See [`Scanny::set_tab_width`], 1 by default.
+    pub fn tab_width(mut self, n: usize) -> Self {
+        self.tab_width = n;
+        self
+    }
+    /// See [`Scanny::set_line_base`], 1 by default.
+    pub fn line_base(mut self, base: usize) -> Self {
+        self.line_base = base;
+        self
+    }
+    /// Number lines from 0.
+    pub fn zero_based_lines(self) -> Self {
+        self.line_base(0)
+    }
+    /// Treat `\r\n` as one line terminator, the default. See
+    /// [`Scanny::set_crlf`].
+    pub fn crlf(mut self) -> Self {
+        self.crlf = true;
+        self
+    }
+    /// Treat only `\n` as a line terminator, so a `\r` before it has its
+    /// own column. See [`Scanny::set_crlf`].
+    pub fn no_crlf(mut self) -> Self {
+        self.crlf = false;
+        self
+    }
+    /// See [`Scanny::set_max_token_bytes`], unlimited by default.
+    pub fn max_token_bytes(mut self, n: usize) -> Self {
+        self.max_token_bytes = n;
+        self
+    }
+    /// Scan ASCII-only input bytewise in [`Scanny::consume_while`], `true`
+    /// by default. Only worth turning off to compare timings.
+    pub fn ascii_fast_path(mut self, v: bool) -> Self {
+        self.ascii_fast_path = v;
+        self
+    }
+    /// See [`Scanny::set_number_format`], `('.', '_')` by default.
+    /// # Panics
+    /// Panics if `decimal_point` and `grouping` are the same char.
+    pub fn number_format(mut self, decimal_point: char, grouping: char) -> Self {
+        assert_ne!(
+            decimal_point, grouping,
+            "the decimal point and the grouping separator must differ"
+        );
+        self.number_format = (decimal_point, grouping);
+        self
+    }
+    pub fn build(self) -> Scanny<'a> {
+        self.build_with_context(())
+    }
+    /// Build a [Scanny] carrying the user context `context`, see
+    /// [`Scanny::with_context`].
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::builder("a\tb").tab_width(4).build_with_context(Vec::<usize>::new());
+    /// sc.bump_bytes(2);
+    /// sc.context_mut().push(sc.position().column);
+    /// assert_eq!(*sc.context(), vec![5]);
+    /// ```
+    pub fn build_with_context<C>(self, context: C) -> Scanny<'a, C> {
+        let sc = Scanny::with_context(self.value, context);
+        sc.set_tab_width(self.tab_width);
+        sc.set_line_base(self.line_base);
+        sc.set_crlf(self.crlf);
+        sc.set_max_token_bytes(self.max_token_bytes);
+        sc.set_ascii_fast_path(self.ascii_fast_path);
+        sc.set_number_format(self.number_format.0, self.number_format.1);
+        sc
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Scanny;
+
+    #[test]
+    fn test_builder() {
+        let src = "a\r\n\tbcdef";
+        let sc = Scanny::builder(src).no_crlf().tab_width(8).build();
+        sc.bump_bytes(2);
+        assert_eq!(sc.position().column, 3);
+        sc.bump_bytes(2);
+        assert_eq!((sc.position().line, sc.position().column), (2, 9));
+
+        let sc = Scanny::builder(src)
+            .line_base(10)
+            .max_token_bytes(2)
+            .ascii_fast_path(false)
+            .build();
+        sc.bump_bytes(4);
+        assert_eq!(sc.position().line, 11);
+        let token = sc.matcher().consume_while(|_| true).finalize(|v| v.value());
+        assert_eq!(token.unwrap().value, "bc");
+
+        let sc = Scanny::builder(src).no_crlf().crlf().build();
+        sc.bump_bytes(2);
+        assert_eq!(sc.position().column, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "must differ")]
+    fn test_builder_number_format() {
+        Scanny::builder("1").number_format(',', ',');
+    }
+}
@@ -0,0 +1,45 @@
+use std::ops::Range;
+
+/// Names and byte ranges of the captured parts of a match.
+pub(crate) type CaptureSpans = Vec<(&'static str, Range<usize>)>;
+
+/// Named parts of a match, recorded with [`Scanny::capture`] and passed
+/// to the closure of [`Scanny::finalize_with_captures`].
+///
+/// [`Scanny::capture`]: crate::Scanny::capture
+/// [`Scanny::finalize_with_captures`]: crate::Scanny::finalize_with_captures
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Captures<'a> {
+    whole: &'a str,
+    spans: CaptureSpans,
+}
+
+impl<'a> Captures<'a> {
+    pub(crate) fn new(whole: &'a str, spans: CaptureSpans) -> Self {
+        Self { whole, spans }
+    }
+    /// Return the text captured as `name`, the last one if it was captured
+    /// several times.
+    pub fn get(&self, name: &str) -> Option<&'a str> {
+        self.span(name).map(|v| &self.whole[v])
+    }
+    /// Return the byte range captured as `name`, the last one if it was
+    /// captured several times.
+    pub fn span(&self, name: &str) -> Option<Range<usize>> {
+        self.spans
+            .iter()
+            .rev()
+            .find(|(v, _)| *v == name)
+            .map(|(_, span)| span.clone())
+    }
+    /// Iterate the names and byte ranges in the order they were captured.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, Range<usize>)> + '_ {
+        self.spans.iter().cloned()
+    }
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+}
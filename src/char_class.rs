@@ -0,0 +1,98 @@
+use std::ops::RangeInclusive;
+
+/// Reusable set of chars, built once and tested with [`CharClass::contains`].
+///
+/// ASCII members are kept in a 128-bit set, so testing an ASCII char is a
+/// single bit lookup.
+/// # Example
+/// ```rust
+/// use scanny::{CharClass, Scanny};
+///
+/// let ident = CharClass::new().ascii_alnum().ch('_').range('α'..='ω');
+/// let sc = Scanny::new("_aβ9 x");
+/// let matched = sc
+///     .matcher()
+///     .consume_class(&ident)
+///     .finalize(|v| v.value())
+///     .unwrap()
+///     .value;
+/// assert_eq!(matched, "_aβ9");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CharClass {
+    ascii: u128,
+    ranges: Vec<RangeInclusive<char>>,
+}
+
+impl CharClass {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Add `ch` to the class.
+    pub fn ch(self, ch: char) -> Self {
+        self.range(ch..=ch)
+    }
+    /// Add every char of `chars` to the class.
+    pub fn chars(self, chars: &str) -> Self {
+        chars.chars().fold(self, Self::ch)
+    }
+    /// Add every char in `range` to the class.
+    pub fn range(mut self, range: RangeInclusive<char>) -> Self {
+        let (start, end) = (*range.start(), *range.end());
+        if start > end {
+            return self;
+        }
+        for ch in start..=end.min('\x7f') {
+            self.ascii |= 1 << ch as u32;
+        }
+        if end > '\x7f' {
+            self.ranges.push(start.max('\u{80}')..=end);
+        }
+        self
+    }
+    pub fn ascii_digit(self) -> Self {
+        self.range('0'..='9')
+    }
+    pub fn ascii_hexdigit(self) -> Self {
+        self.range('0'..='9').range('a'..='f').range('A'..='F')
+    }
+    pub fn ascii_alpha(self) -> Self {
+        self.range('a'..='z').range('A'..='Z')
+    }
+    pub fn ascii_alnum(self) -> Self {
+        self.ascii_alpha().ascii_digit()
+    }
+    pub fn ascii_whitespace(self) -> Self {
+        self.chars(" \t\n\x0c\r")
+    }
+    /// Return `true` if `ch` is in the class.
+    pub fn contains(&self, ch: char) -> bool {
+        if ch.is_ascii() {
+            self.ascii & (1 << ch as u32) != 0
+        } else {
+            self.ranges.iter().any(|v| v.contains(&ch))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CharClass;
+
+    #[test]
+    fn test_contains() {
+        let class = CharClass::new()
+            .ascii_hexdigit()
+            .ch('_')
+            .range('x'..='ä')
+            .range('z'..='a');
+        for ch in "09afAF_xyz\x7f\u{80}ä".chars() {
+            assert!(class.contains(ch), "{ch:?}");
+        }
+        for ch in "gG-w\0å€".chars() {
+            assert!(!class.contains(ch), "{ch:?}");
+        }
+        assert!(CharClass::new().ascii_whitespace().contains('\t'));
+        assert!(!CharClass::new().contains('a'));
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{pos::WithPos, scanner::Scanny};
+
+impl<'a, C> Scanny<'a, C> {
+    /// Consume an ISO 8601 calendar date `YYYY-MM-DD` and return its year,
+    /// month and day.
+    ///
+    /// The month must be in `1..=12` and the day in `1..=31`, the day is
+    /// not checked against the month. Return `None` without consuming if
+    /// the input does not start with such a date.
+    /// Like [`Scanny::bump`] this moves the active matcher if there is one.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("2024-02-29 INFO started");
+    /// let date = sc.match_iso_date().unwrap();
+    /// assert_eq!(date.value, (2024, 2, 29));
+    /// assert_eq!(date.get_byte_pos(), 0..10);
+    /// assert_eq!(sc.peek(), Some(' '));
+    /// ```
+    pub fn match_iso_date(&self) -> Option<WithPos<(u16, u8, u8)>> {
+        let rest = self.rest().as_bytes();
+        let date = rest.get(..10)?;
+        let is_digit = |i: usize| date[i].is_ascii_digit();
+        if !(0..4).chain([5, 6, 8, 9]).all(is_digit) || date[4] != b'-' || date[7] != b'-' {
+            return None;
+        }
+        let number = |digits: &[u8]| {
+            digits
+                .iter()
+                .fold(0u16, |n, v| n * 10 + u16::from(v - b'0'))
+        };
+        let year = number(&date[..4]);
+        let month = number(&date[5..7]) as u8;
+        let day = number(&date[8..]) as u8;
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+        let start = self.save();
+        self.bump_bytes(date.len());
+        Some(self.with_pos_since(&start, (year, month, day)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Scanny;
+
+    #[test]
+    fn test_match_iso_date() {
+        let sc = Scanny::new("1999-12-31\n0001-01-01");
+        let date = sc.match_iso_date().unwrap();
+        assert_eq!(date.value, (1999, 12, 31));
+        assert_eq!(date.get_line_pos(), 1..=1);
+        sc.bump();
+        let date = sc.match_iso_date().unwrap();
+        assert_eq!(date.value, (1, 1, 1));
+        assert_eq!(date.get_byte_pos(), 11..21);
+        assert!(sc.at_end());
+
+        for src in [
+            "2024-1-01",
+            "2024/01/01",
+            "24-01-01",
+            "2024-01-0x",
+            "２024-01-01",
+            "2024-01",
+            "",
+        ] {
+            let sc = Scanny::new(src);
+            assert_eq!(sc.match_iso_date(), None, "{src}");
+            assert_eq!(sc.position().byte, 0);
+        }
+
+        for src in ["2024-00-10", "2024-13-10", "2024-01-00", "2024-01-32"] {
+            let sc = Scanny::new(src);
+            assert_eq!(sc.match_iso_date(), None, "{src}");
+            assert_eq!(sc.position().byte, 0);
+        }
+
+        let sc = Scanny::new("2024-01-011");
+        assert_eq!(sc.match_iso_date().unwrap().value, (2024, 1, 1));
+        assert_eq!(sc.peek(), Some('1'));
+
+        let sc = Scanny::new("on 2024-06-01.");
+        sc.matcher().then('o').then('n').then(' ');
+        let date = sc.match_iso_date().unwrap();
+        assert_eq!(date.get_byte_pos(), 3..13);
+        let token = sc.then('.').finalize(|v| v.is_matched()).unwrap();
+        assert!(token.value);
+        assert_eq!(token.get_byte_pos(), 0..14);
+    }
+}
@@ -0,0 +1,21 @@
+use std::{error::Error, fmt};
+
+/// Error returned by [`Scanny::from_parts`](crate::Scanny::from_parts).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanError {
+    /// The byte offset is past the end of the input or not on a char
+    /// boundary.
+    InvalidPosition { byte: usize },
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidPosition { byte } => {
+                write!(f, "byte {byte} is not a char boundary of the input")
+            }
+        }
+    }
+}
+
+impl Error for ScanError {}
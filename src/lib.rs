@@ -1,6 +1,30 @@
 #![doc = include_str!("../README.md")]
 
+mod builder;
+mod captures;
+mod char_class;
+#[cfg(feature = "datetime")]
+mod datetime;
+mod error;
+mod operator;
 mod pos;
+#[cfg(feature = "regex")]
+mod regex_match;
 mod scanner;
-pub use pos::WithPos;
+#[cfg(feature = "profiling")]
+mod stats;
+mod stream;
+#[cfg(feature = "intern")]
+mod symbol;
+pub use builder::ScannyBuilder;
+pub use captures::Captures;
+pub use char_class::CharClass;
+pub use error::ScanError;
+pub use operator::OperatorSet;
+pub use pos::{Position, Span, WithPos};
 pub use scanner::{MatchType, Scanny};
+#[cfg(feature = "profiling")]
+pub use stats::ScanStats;
+pub use stream::StreamScanny;
+#[cfg(feature = "intern")]
+pub use symbol::{Symbol, SymbolTable};
@@ -0,0 +1,78 @@
+use crate::{pos::WithPos, scanner::Scanny};
+
+/// Set of operators matched longest first by [`Scanny::match_operator`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OperatorSet {
+    /// Sorted by length, longest first, without duplicates.
+    ops: Vec<Box<str>>,
+}
+
+impl OperatorSet {
+    /// Build the set from `ops` in any order. Empty operators are ignored.
+    pub fn new(ops: &[&str]) -> Self {
+        let mut ops: Vec<Box<str>> = ops
+            .iter()
+            .filter(|v| !v.is_empty())
+            .map(|v| Box::from(*v))
+            .collect();
+        ops.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        ops.dedup();
+        Self { ops }
+    }
+    /// Return the longest operator `s` starts with.
+    pub fn longest_prefix(&self, s: &str) -> Option<&str> {
+        self.ops.iter().find(|v| s.starts_with(&***v)).map(|v| &**v)
+    }
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+}
+
+impl<'a, C> Scanny<'a, C> {
+    /// Consume the longest operator of `ops` the input starts with and
+    /// return it. Return `None` without consuming if there is none.
+    /// # Example
+    /// ```rust
+    /// use scanny::{OperatorSet, Scanny};
+    ///
+    /// let ops = OperatorSet::new(&["=", "==", "===", "=>"]);
+    /// let sc = Scanny::new("===>");
+    /// let op = sc.match_operator(&ops).unwrap();
+    /// assert_eq!((op.value, op.get_byte_pos()), ("===", 0..3));
+    /// assert_eq!(sc.match_operator(&ops), None);
+    /// assert_eq!(sc.peek(), Some('>'));
+    /// ```
+    pub fn match_operator(&self, ops: &OperatorSet) -> Option<WithPos<&'a str>> {
+        let len = ops.longest_prefix(self.rest())?.len();
+        let start = self.save();
+        let op = self.bump_bytes(len);
+        Some(self.with_pos_since(&start, op))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OperatorSet;
+    use crate::Scanny;
+
+    #[test]
+    fn test_match_operator() {
+        let ops = OperatorSet::new(&["=>", "=", "===", "==", "", "=="]);
+        assert_eq!(ops.len(), 4);
+        let sc = Scanny::new("= == === => ====");
+        let mut found = Vec::new();
+        while !sc.at_end() {
+            sc.skip_hspace();
+            found.push(sc.match_operator(&ops).unwrap().value);
+        }
+        assert_eq!(found, vec!["=", "==", "===", "=>", "===", "="]);
+
+        let sc = Scanny::new("a=");
+        sc.matcher().then('a');
+        assert_eq!(sc.match_operator(&ops).unwrap().get_byte_pos(), 1..2);
+        assert_eq!(sc.finalize(|v| v.value()).unwrap().value, "a=");
+    }
+}
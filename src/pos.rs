@@ -1,10 +1,52 @@
-use std::ops::{Range, RangeInclusive};
+use std::ops::{Deref, DerefMut, Range, RangeInclusive};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct WithPos<T> {
     pub value: T,
     byte_pos: Range<usize>,
     line_pos: RangeInclusive<usize>,
+    char_pos: Range<usize>,
+    note: Option<String>,
+}
+
+/// Return the UTF-16 code unit offset of the byte offset `byte` in `source`.
+pub(crate) fn utf16_offset(source: &str, byte: usize) -> usize {
+    source[..byte].chars().map(char::len_utf16).sum()
+}
+
+/// A point in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Position {
+    /// Byte offset from the start of the input.
+    pub byte: usize,
+    /// Line number, starting at 1 unless changed with
+    /// [`Scanny::set_line_base`](crate::Scanny::set_line_base).
+    pub line: usize,
+    /// Column, starting at 1. Each char counts as one column, except a
+    /// tab, which advances to the next multiple of the tab width set with
+    /// [`Scanny::set_tab_width`](crate::Scanny::set_tab_width), and the `\n`
+    /// of a `\r\n`, which has the column of the `\r`, see
+    /// [`Scanny::set_crlf`](crate::Scanny::set_crlf).
+    pub column: usize,
+}
+
+/// Byte and line range of a token, without the token value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Span {
+    byte_pos: Range<usize>,
+    line_pos: RangeInclusive<usize>,
+}
+
+impl Span {
+    pub fn new(byte_pos: Range<usize>, line_pos: RangeInclusive<usize>) -> Self {
+        Self { byte_pos, line_pos }
+    }
+    pub fn get_byte_pos(&self) -> Range<usize> {
+        self.byte_pos.clone()
+    }
+    pub fn get_line_pos(&self) -> RangeInclusive<usize> {
+        self.line_pos.clone()
+    }
 }
 
 impl<T> From<(T, Range<usize>, RangeInclusive<usize>)> for WithPos<T> {
@@ -13,6 +55,8 @@ impl<T> From<(T, Range<usize>, RangeInclusive<usize>)> for WithPos<T> {
             value: value.0,
             byte_pos: value.1,
             line_pos: value.2,
+            char_pos: 0..0,
+            note: None,
         }
     }
 }
@@ -23,16 +67,45 @@ impl<T> From<(T, RangeInclusive<usize>, Range<usize>)> for WithPos<T> {
             value: value.0,
             byte_pos: value.2,
             line_pos: value.1,
+            char_pos: 0..0,
+            note: None,
         }
     }
 }
 
+/// Gives access to the methods of the value.
+/// # Example
+/// ```rust
+/// use scanny::Scanny;
+///
+/// let sc = Scanny::new("abc");
+/// let token = sc.matcher().then('a').finalize(|v| v).unwrap();
+/// assert!(token.is_matched());
+/// assert_eq!(token.value(), "a");
+/// ```
+impl<T> Deref for WithPos<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+/// The positions do not depend on the value, so it can be changed freely,
+/// like through the public `value` field.
+impl<T> DerefMut for WithPos<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
 impl<T> WithPos<T> {
     pub fn new(value: T) -> Self {
         Self {
             value,
             byte_pos: 0..0,
             line_pos: 0..=0,
+            char_pos: 0..0,
+            note: None,
         }
     }
     pub fn set_byte_pos(mut self, pos: Range<usize>) -> Self {
@@ -49,4 +122,197 @@ impl<T> WithPos<T> {
     pub fn get_line_pos(&self) -> RangeInclusive<usize> {
         self.line_pos.clone()
     }
+    pub fn set_char_pos(mut self, pos: Range<usize>) -> Self {
+        self.char_pos = pos;
+        self
+    }
+    /// Return the range in chars, counted from the start of the input.
+    ///
+    /// Unlike the byte range this counts a multi-byte char once, as
+    /// needed to talk to tools using char offsets.
+    pub fn get_char_pos(&self) -> Range<usize> {
+        self.char_pos.clone()
+    }
+    /// Return the line range as a half-open `Range`.
+    ///
+    /// The end is one past the last line, so a token on line `3..=3`
+    /// gives `3..4`.
+    pub fn line_range(&self) -> Range<usize> {
+        *self.line_pos.start()..*self.line_pos.end() + 1
+    }
+    /// Return `true` if the byte `offset` is inside the byte range.
+    pub const fn contains_byte(&self, offset: usize) -> bool {
+        self.byte_pos.start <= offset && offset < self.byte_pos.end
+    }
+    /// Return `true` if the byte range shares at least one byte with
+    /// `other`. An empty range overlaps nothing.
+    pub const fn overlaps(&self, other: &Range<usize>) -> bool {
+        self.byte_pos.start < other.end
+            && other.start < self.byte_pos.end
+            && self.byte_pos.start < self.byte_pos.end
+            && other.start < other.end
+    }
+    /// Return the range in UTF-16 code units, as used by LSP, within
+    /// `source`, the input this value was scanned from.
+    ///
+    /// This walks `source` from the start, so it is `O(n)`. For many
+    /// lookups cache the offsets of line starts and convert from there.
+    /// # Panics
+    /// Panics if the byte range does not fit `source` or is not on char
+    /// boundaries.
+    pub fn utf16_range(&self, source: &str) -> Range<usize> {
+        let start = utf16_offset(source, self.byte_pos.start);
+        let len = utf16_offset(&source[self.byte_pos.start..], self.byte_pos.len());
+        start..start + len
+    }
+    /// Split into the value, the byte range and the line range.
+    ///
+    /// The char range and the note are dropped, so converting the parts
+    /// back with `From` gives them their defaults. Use [`Self::map`] to
+    /// change the value while keeping every position.
+    pub fn into_parts(self) -> (T, Range<usize>, RangeInclusive<usize>) {
+        (self.value, self.byte_pos, self.line_pos)
+    }
+    /// Apply `f` to the value, keeping the positions.
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> WithPos<U> {
+        WithPos {
+            value: f(self.value),
+            byte_pos: self.byte_pos,
+            line_pos: self.line_pos,
+            char_pos: self.char_pos,
+            note: self.note,
+        }
+    }
+    /// Replace the value, keeping the positions. Sugar over
+    /// `map(|_| value)`.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// enum Token {
+    ///     Let,
+    /// }
+    /// let sc = Scanny::new("let");
+    /// let kw = sc.matcher().consume_while(char::is_ascii_alphabetic).finalize(|v| v.value());
+    /// let kw = kw.unwrap().with_value(Token::Let);
+    /// assert_eq!(kw.value, Token::Let);
+    /// assert_eq!(kw.get_byte_pos(), 0..3);
+    /// ```
+    pub fn with_value<U>(self, value: U) -> WithPos<U> {
+        self.map(|_| value)
+    }
+    /// Attach a note, like a warning about a deprecated escape, so it
+    /// travels with the token. Replaces any previous note.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("var x");
+    /// let kw = sc.matcher().consume_while(char::is_ascii_alphabetic).finalize(|v| v.value());
+    /// let kw = kw.unwrap();
+    /// let kw = if kw.value == "var" { kw.with_note("`var` is deprecated") } else { kw };
+    /// assert_eq!(kw.note(), Some("`var` is deprecated"));
+    /// assert_eq!(kw.get_byte_pos(), 0..3);
+    /// ```
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.note = Some(note.into());
+        self
+    }
+    /// Return the note attached with [`Self::with_note`], if any.
+    pub fn note(&self) -> Option<&str> {
+        self.note.as_deref()
+    }
+    /// Return the [Span] of this value.
+    pub fn span(&self) -> Span {
+        Span::new(self.byte_pos.clone(), self.line_pos.clone())
+    }
+    /// Return the text of `source` covered by this value.
+    ///
+    /// `source` must be the input this value was scanned from, an empty
+    /// str is returned if the byte range does not fit it.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let src = "  42 ";
+    /// let sc = Scanny::new(src);
+    /// let num = sc
+    ///     .skeep_while(char::is_whitespace)
+    ///     .matcher()
+    ///     .consume_while(char::is_ascii_digit)
+    ///     .finalize(|v| v.value().parse::<u32>().unwrap())
+    ///     .unwrap();
+    /// assert_eq!(num.value, 42);
+    /// assert_eq!(num.text(src), "42");
+    /// ```
+    pub fn text<'s>(&self, source: &'s str) -> &'s str {
+        source.get(self.byte_pos.clone()).unwrap_or("")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::WithPos;
+
+    #[test]
+    fn test_hash_key() {
+        let a = WithPos::new("foo").set_byte_pos(0..3).set_line_pos(1..=1);
+        let b = WithPos::new("foo").set_byte_pos(4..7).set_line_pos(1..=1);
+        let mut cache = HashMap::new();
+        cache.insert(a.clone(), 1);
+        cache.insert(b.clone(), 2);
+        cache.insert(a.clone(), 3);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&a), Some(&3));
+        assert_eq!(WithPos::from(a.clone().into_parts()), a);
+
+        let mut spans = HashMap::new();
+        spans.insert(a.span(), "a");
+        spans.insert(b.span(), "b");
+        assert_eq!(spans.get(&b.span()), Some(&"b"));
+        assert_eq!(spans.get(&a.clone().set_byte_pos(1..3).span()), None);
+    }
+
+    #[test]
+    fn test_contains_and_overlaps() {
+        let token = WithPos::new(()).set_byte_pos(3..6);
+        assert!(!token.contains_byte(2));
+        assert!(token.contains_byte(3));
+        assert!(token.contains_byte(5));
+        assert!(!token.contains_byte(6));
+        assert!(token.overlaps(&(0..4)));
+        assert!(token.overlaps(&(5..9)));
+        assert!(token.overlaps(&(4..5)));
+        assert!(!token.overlaps(&(0..3)));
+        assert!(!token.overlaps(&(6..9)));
+        assert!(!token.overlaps(&(4..4)));
+        assert!(!WithPos::new(()).set_byte_pos(4..4).overlaps(&(0..9)));
+    }
+
+    #[test]
+    fn test_map() {
+        let token = WithPos::new("12")
+            .set_byte_pos(3..5)
+            .set_line_pos(2..=2)
+            .set_char_pos(3..5);
+        let num = token.clone().map(|v| v.parse::<u32>().unwrap());
+        assert_eq!(num.value, 12);
+        assert_eq!(num.span(), token.span());
+        assert_eq!(num.get_char_pos(), 3..5);
+        assert_eq!(token.clone().with_value(()).span(), token.span());
+    }
+
+    #[test]
+    fn test_note() {
+        let token = WithPos::new("x").set_byte_pos(0..1);
+        assert_eq!(token.note(), None);
+        let token = token.with_note("first").with_note(String::from("second"));
+        assert_eq!(token.note(), Some("second"));
+        let token = token.map(str::len);
+        assert_eq!(token.note(), Some("second"));
+        assert_ne!(token.clone(), WithPos::new(1).set_byte_pos(0..1));
+    }
 }
@@ -0,0 +1,68 @@
+use regex_automata::{Anchored, Input, meta::Regex};
+
+use crate::scanner::Scanny;
+
+impl<'a, C> Scanny<'a, C> {
+    /// match `re` at the current position, consume on match.
+    ///
+    /// The search is anchored at the current position, so the pattern does
+    /// not need a `^` and a failing match does not scan the rest of the
+    /// input.
+    /// # Example
+    /// ```rust
+    /// use regex_automata::meta::Regex;
+    /// use scanny::Scanny;
+    ///
+    /// let ident = Regex::new(r"[A-Za-z_][A-Za-z0-9_]*").unwrap();
+    /// let sc = Scanny::new("foo_1 2");
+    /// let token = sc.matcher().match_regex(&ident).finalize(|v| v.value()).unwrap();
+    /// assert_eq!(token.value, "foo_1");
+    /// ```
+    pub fn match_regex(&self, re: &Regex) -> &Self {
+        self.custom(|rest| {
+            re.search(&Input::new(rest).anchored(Anchored::Yes))
+                .map(|m| m.end())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use regex_automata::meta::Regex;
+
+    use crate::Scanny;
+
+    #[test]
+    fn test_match_regex() {
+        let comment = Regex::new(r"/\*(?s:.)*?\*/").unwrap();
+        let sc = Scanny::new("/* a\nb */x /* open");
+        let token = sc
+            .matcher()
+            .match_regex(&comment)
+            .finalize(|v| v.is_matched())
+            .unwrap();
+        assert!(token.value);
+        assert_eq!(token.get_byte_pos(), 0..9);
+        assert_eq!(token.get_line_pos(), 1..=2);
+        assert_eq!(sc.peek(), Some('x'));
+
+        sc.bump();
+        sc.bump();
+        let token = sc
+            .matcher()
+            .match_regex(&comment)
+            .finalize(|v| v.is_matched())
+            .unwrap();
+        assert!(!token.value);
+        assert_eq!(sc.peek(), Some('/'));
+
+        // a match later in the input does not count
+        let digits = Regex::new(r"\d+").unwrap();
+        let sc = Scanny::new("a1");
+        let token = sc
+            .matcher()
+            .match_regex(&digits)
+            .finalize(|v| v.is_matched());
+        assert!(!token.unwrap().value);
+    }
+}
@@ -1,6 +1,33 @@
-use std::{cell::RefCell, char, rc::Rc, str::Chars};
+use std::{
+    cell::{Ref, RefCell, RefMut},
+    char,
+    ops::{ControlFlow, Range, RangeInclusive},
+    rc::Rc,
+};
 
-use crate::pos::WithPos;
+#[cfg(feature = "profiling")]
+use crate::stats::ScanStats;
+use crate::{
+    builder::ScannyBuilder,
+    captures::{CaptureSpans, Captures},
+    char_class::CharClass,
+    error::ScanError,
+    pos::{self, Position, Span, WithPos},
+};
+
+/// Add `n`, 1 by default, to a counter of the [`ScanStats`] with the
+/// `profiling` feature, do nothing otherwise.
+macro_rules! count {
+    ($sc:expr, $field:ident) => {
+        count!($sc, $field, 1)
+    };
+    ($sc:expr, $field:ident, $n:expr) => {
+        #[cfg(feature = "profiling")]
+        {
+            $sc.stats.borrow_mut().$field += $n;
+        }
+    };
+}
 
 pub enum MatchType<'a> {
     /// All matched
@@ -29,6 +56,26 @@ impl<'a> MatchType<'a> {
             Self::Few(_, _) => true,
         }
     }
+    /// Return `true` if the match produced no text, like an attempt at the
+    /// end of the input, so a token loop can stop.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("12 ");
+    /// let mut numbers = Vec::new();
+    /// loop {
+    ///     sc.skeep_while(char::is_whitespace);
+    ///     let token = sc.matcher().match_char(char::is_ascii_digit).consume_while(char::is_ascii_digit);
+    ///     let token = token.finalize(|v| (!v.is_empty()).then(|| v.value())).unwrap();
+    ///     let Some(value) = token.value else { break };
+    ///     numbers.push(value);
+    /// }
+    /// assert_eq!(numbers, vec!["12"]);
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.value().is_empty()
+    }
     /// true (default): on matched, consume match part.
     /// false: on matched, does not consume match part.
     pub fn consume_on_match(&self, v: bool) {
@@ -46,39 +93,110 @@ impl<'a> MatchType<'a> {
 }
 
 #[derive(Clone)]
-struct Matcher<'a> {
-    chars: Rc<RefCell<Chars<'a>>>,
+struct Matcher {
     byte_pos: Rc<RefCell<usize>>,
+    char_pos: Rc<RefCell<usize>>,
     line: Rc<RefCell<usize>>,
     is_matched: Rc<RefCell<bool>>,
     match_next: Rc<RefCell<bool>>,
+    snapshot_pos: Rc<RefCell<usize>>,
+    consume_on_match: Rc<RefCell<bool>>,
+    consume_on_not_match: Rc<RefCell<bool>>,
+    captures: Rc<RefCell<CaptureSpans>>,
 }
 
+/// Saved cursor of a [Scanny] or of its active matcher. The byte offset
+/// is the cursor, the rest of the input is sliced from it.
 #[derive(Clone)]
-pub struct Scanny<'a> {
+pub(crate) struct State {
+    byte_pos: usize,
+    char_pos: usize,
+    line: usize,
+}
+
+type TraceFn = Rc<dyn Fn(&str, Position)>;
+
+/// Scanner over a string slice.
+///
+/// `C` is user context shared by every clone of the scanner, see
+/// [`Scanny::with_context`].
+///
+/// Predicates and sub-matchers are taken as `F: Fn(..)`, so a reference
+/// to a closure works as well and one closure can be reused across calls
+/// without moving it. Only callbacks that are meant to keep state, like
+/// [`Scanny::consume_each`], take `FnMut`.
+/// # Example
+/// ```rust
+/// use scanny::Scanny;
+///
+/// let extra = String::from("_$");
+/// let is_ident = move |v: &char| v.is_ascii_alphanumeric() || extra.contains(*v);
+/// let sc = Scanny::new("foo_1 bar");
+/// let first = sc.matcher().consume_while(&is_ident).finalize(|v| v.value());
+/// sc.skeep_while(char::is_whitespace);
+/// let second = sc.matcher().match_char(&is_ident).then_while(&is_ident).finalize(|v| v.value());
+/// assert_eq!(first.unwrap().value, "foo_1");
+/// assert_eq!(second.unwrap().value, "bar");
+/// ```
+pub struct Scanny<'a, C = ()> {
     whole: &'a str,
-    chars: Rc<RefCell<Chars<'a>>>,
     byte_pos: Rc<RefCell<usize>>,
+    char_pos: Rc<RefCell<usize>>,
     line: Rc<RefCell<usize>>,
-    matcher: Rc<RefCell<Option<Matcher<'a>>>>,
+    matcher: Rc<RefCell<Option<Matcher>>>,
+    max_token_bytes: Rc<RefCell<usize>>,
+    trace: Rc<RefCell<Option<TraceFn>>>,
+    crlf: Rc<RefCell<bool>>,
+    tab_width: Rc<RefCell<usize>>,
+    char_count: Rc<RefCell<Option<usize>>>,
+    line_base: Rc<RefCell<usize>>,
+    ascii: Rc<RefCell<Option<bool>>>,
+    line_starts: Rc<RefCell<Option<Vec<usize>>>>,
+    last_span: Rc<RefCell<Option<Span>>>,
+    number_format: Rc<RefCell<(char, char)>>,
+    #[cfg(feature = "profiling")]
+    pub(crate) stats: Rc<RefCell<ScanStats>>,
+    context: Rc<RefCell<C>>,
+}
+
+/// Clones share the input, the position and the context.
+impl<C> Clone for Scanny<'_, C> {
+    fn clone(&self) -> Self {
+        Self {
+            whole: self.whole,
+            byte_pos: Rc::clone(&self.byte_pos),
+            char_pos: Rc::clone(&self.char_pos),
+            line: Rc::clone(&self.line),
+            matcher: Rc::clone(&self.matcher),
+            max_token_bytes: Rc::clone(&self.max_token_bytes),
+            trace: Rc::clone(&self.trace),
+            crlf: Rc::clone(&self.crlf),
+            tab_width: Rc::clone(&self.tab_width),
+            char_count: Rc::clone(&self.char_count),
+            line_base: Rc::clone(&self.line_base),
+            ascii: Rc::clone(&self.ascii),
+            line_starts: Rc::clone(&self.line_starts),
+            last_span: Rc::clone(&self.last_span),
+            number_format: Rc::clone(&self.number_format),
+            #[cfg(feature = "profiling")]
+            stats: Rc::clone(&self.stats),
+            context: Rc::clone(&self.context),
+        }
+    }
 }
 
 impl<'a> From<&'a str> for Scanny<'a> {
     #[inline]
     fn from(value: &'a str) -> Self {
-        Self {
-            whole: value,
-            chars: Rc::new(RefCell::new(value.chars())),
-            byte_pos: Rc::new(RefCell::new(0)),
-            line: Rc::new(RefCell::new(1)),
-            matcher: Rc::new(RefCell::new(None)),
-        }
+        Self::with_context(value, ())
     }
 }
 
 impl<'a> Scanny<'a> {
     /// Creates a new [Scanny] instance from a given string slice.
     ///
+    /// Use [`Scanny::builder`] to change the defaults.
+    ///
     /// # Example
     /// ```rust
     /// use scanny::Scanny;
@@ -89,6 +207,140 @@ impl<'a> Scanny<'a> {
     pub fn new(value: &'a str) -> Self {
         Self::from(value)
     }
+    /// Return a [ScannyBuilder] to configure a new [Scanny] over `value`.
+    pub fn builder(value: &'a str) -> ScannyBuilder<'a> {
+        ScannyBuilder::new(value)
+    }
+    /// Creates a new [Scanny] instance over `whole`, positioned at the byte
+    /// offset `byte_pos`. Lines and chars are counted from the start of
+    /// `whole`, so positions are those of the whole document.
+    ///
+    /// Return [`ScanError::InvalidPosition`] if `byte_pos` is past the end
+    /// of `whole` or not on a char boundary.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let doc = "a\nbé c";
+    /// let sc = Scanny::from_parts(doc, 5).unwrap();
+    /// assert_eq!(sc.peek(), Some(' '));
+    /// assert_eq!(sc.position().line, 2);
+    /// assert_eq!(sc.chars_consumed(), 4);
+    /// assert!(Scanny::from_parts(doc, 4).is_err());
+    /// ```
+    pub fn from_parts(whole: &'a str, byte_pos: usize) -> Result<Self, ScanError> {
+        if !whole.is_char_boundary(byte_pos) {
+            return Err(ScanError::InvalidPosition { byte: byte_pos });
+        }
+        let sc = Self::from(whole);
+        sc.bump_bytes(byte_pos);
+        Ok(sc)
+    }
+}
+
+impl<'a, C> Scanny<'a, C> {
+    /// Creates a new [Scanny] instance carrying the user context `context`.
+    ///
+    /// Combinators handing the scanner to a closure, like
+    /// [`Scanny::then_peek`], give access to the context there, e.g. to
+    /// collect diagnostics.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::with_context("a1b", Vec::new());
+    /// while let Some(ch) = sc.bump() {
+    ///     if !ch.is_ascii_alphabetic() {
+    ///         let pos = sc.position().byte - 1;
+    ///         sc.context_mut().push(pos);
+    ///     }
+    /// }
+    /// assert_eq!(*sc.context(), vec![1]);
+    /// ```
+    pub fn with_context(value: &'a str, context: C) -> Self {
+        Self::with_shared_context(value, Rc::new(RefCell::new(context)))
+    }
+    /// Creates a new [Scanny] instance sharing `context` with other
+    /// scanners.
+    fn with_shared_context(value: &'a str, context: Rc<RefCell<C>>) -> Self {
+        Self {
+            whole: value,
+            byte_pos: Rc::new(RefCell::new(0)),
+            char_pos: Rc::new(RefCell::new(0)),
+            line: Rc::new(RefCell::new(1)),
+            matcher: Rc::new(RefCell::new(None)),
+            max_token_bytes: Rc::new(RefCell::new(usize::MAX)),
+            trace: Rc::new(RefCell::new(None)),
+            crlf: Rc::new(RefCell::new(true)),
+            tab_width: Rc::new(RefCell::new(1)),
+            char_count: Rc::new(RefCell::new(None)),
+            line_base: Rc::new(RefCell::new(1)),
+            ascii: Rc::new(RefCell::new(None)),
+            line_starts: Rc::new(RefCell::new(None)),
+            last_span: Rc::new(RefCell::new(None)),
+            number_format: Rc::new(RefCell::new(('.', '_'))),
+            #[cfg(feature = "profiling")]
+            stats: Rc::new(RefCell::new(ScanStats::default())),
+            context,
+        }
+    }
+    /// Borrow the user context.
+    /// # Panics
+    /// Panics if the context is mutably borrowed.
+    pub fn context(&self) -> Ref<'_, C> {
+        self.context.borrow()
+    }
+    /// Mutably borrow the user context.
+    /// # Panics
+    /// Panics if the context is already borrowed.
+    pub fn context_mut(&self) -> RefMut<'_, C> {
+        self.context.borrow_mut()
+    }
+    /// Creates a new [Scanny] over the bytes `range` of the input, to scan
+    /// a region like the body of a block with its own grammar.
+    ///
+    /// The new scanner starts at `range.start` and ends at `range.end`,
+    /// but counts lines, columns and offsets from the start of the whole
+    /// input, so its tokens have the same positions as in `self`. The line
+    /// base, tab width, `\r\n` handling and number format are copied and
+    /// the user context is shared, the position of `self` does not change.
+    /// # Panics
+    /// Panics if `range` does not fit the input or is not on char
+    /// boundaries.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("f {\n  x }");
+    /// let body = sc.subscanner(3..8);
+    /// let x = body
+    ///     .skeep_while(char::is_whitespace)
+    ///     .matcher()
+    ///     .then('x')
+    ///     .finalize(|v| v.value())
+    ///     .unwrap();
+    /// assert_eq!(x.get_byte_pos(), 6..7);
+    /// assert_eq!(x.get_line_pos(), 2..=2);
+    /// assert_eq!(body.skip_hspace().peek(), None);
+    /// ```
+    pub fn subscanner(&self, range: Range<usize>) -> Self {
+        let Range { start, end } = range;
+        assert!(
+            start <= end && end <= self.whole.len(),
+            "range {start}..{end} is out of bounds of the input"
+        );
+        assert!(
+            self.whole.is_char_boundary(start) && self.whole.is_char_boundary(end),
+            "range {start}..{end} is not on char boundaries"
+        );
+        let sc = Self::with_shared_context(&self.whole[..end], Rc::clone(&self.context));
+        sc.bump_bytes(start);
+        sc.set_line_base(*self.line_base.borrow());
+        sc.set_crlf(*self.crlf.borrow());
+        sc.set_tab_width(*self.tab_width.borrow());
+        *sc.number_format.borrow_mut() = *self.number_format.borrow();
+        sc
+    }
     fn next_match(&self) -> bool {
         let m = self.matcher.borrow().clone();
         if let Some(matcher) = m {
@@ -106,7 +358,467 @@ impl<'a> Scanny<'a> {
         }
         *m.as_mut().unwrap().match_next.borrow_mut() = v;
     }
+    /// Return the unconsumed part of the input.
+    pub(crate) fn rest(&self) -> &'a str {
+        &self.whole[self.byte_pos()..]
+    }
+    /// Return the byte offset of the active matcher, or of the scanner if
+    /// there is no matcher. Unlike [`Self::position`] no column is computed.
+    fn byte_pos(&self) -> usize {
+        match self.matcher.borrow().as_ref() {
+            Some(m) => *m.byte_pos.borrow(),
+            None => *self.byte_pos.borrow(),
+        }
+    }
+    /// Save the cursor of the active matcher, or of the scanner if there is
+    /// no matcher.
+    pub(crate) fn save(&self) -> State {
+        let binding = self.matcher.borrow();
+        let (byte_pos, char_pos, line) = match binding.as_ref() {
+            Some(m) => (&m.byte_pos, &m.char_pos, &m.line),
+            None => (&self.byte_pos, &self.char_pos, &self.line),
+        };
+        State {
+            byte_pos: *byte_pos.borrow(),
+            char_pos: *char_pos.borrow(),
+            line: *line.borrow(),
+        }
+    }
+    /// Move the cursor of the active matcher, or of the scanner if there is
+    /// no matcher, back to `state`.
+    fn restore(&self, state: State) {
+        let binding = self.matcher.borrow();
+        let (byte_pos, char_pos, line) = match binding.as_ref() {
+            Some(m) => (&m.byte_pos, &m.char_pos, &m.line),
+            None => (&self.byte_pos, &self.char_pos, &self.line),
+        };
+        count!(
+            self,
+            backtracks,
+            (state.byte_pos < *byte_pos.borrow()) as usize
+        );
+        *byte_pos.borrow_mut() = state.byte_pos;
+        *char_pos.borrow_mut() = state.char_pos;
+        *line.borrow_mut() = state.line;
+    }
+    /// Wrap `value` with the positions from `start` to the current position.
+    pub(crate) fn with_pos_since<T>(&self, start: &State, value: T) -> WithPos<T> {
+        let end = self.save();
+        WithPos::new(value)
+            .set_byte_pos(start.byte_pos..end.byte_pos)
+            .set_line_pos(start.line..=end.line)
+            .set_char_pos(start.char_pos..end.char_pos)
+    }
+    /// Return the column, in chars starting at 1, of the byte offset `byte`.
+    fn column_at(&self, byte: usize) -> usize {
+        debug_assert!(self.whole.is_char_boundary(byte));
+        let line_start = self.whole[..byte].rfind('\n').map_or(0, |v| v + 1);
+        let mut line = &self.whole[line_start..byte];
+        if *self.crlf.borrow() && self.whole[byte..].starts_with('\n') {
+            line = line.strip_suffix('\r').unwrap_or(line);
+        }
+        let tab = *self.tab_width.borrow();
+        if tab <= 1 {
+            return line.chars().count() + 1;
+        }
+        line.chars().fold(0, |col, ch| match ch {
+            '\t' => col + tab - col % tab,
+            _ => col + 1,
+        }) + 1
+    }
+    /// Number lines from `base` instead of 1, e.g. 0 for 0-based
+    /// protocols. All line numbers reported afterwards use the new base,
+    /// including the current line.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("a\nb");
+    /// sc.set_line_base(0);
+    /// assert_eq!(sc.position().line, 0);
+    /// sc.skeep_while(|v| v != 'b');
+    /// let b = sc.matcher().then('b').finalize(|_| ()).unwrap();
+    /// assert_eq!(b.get_line_pos(), 1..=1);
+    /// ```
+    pub fn set_line_base(&self, base: usize) {
+        let old = std::mem::replace(&mut *self.line_base.borrow_mut(), base);
+        let rebase = |line: &RefCell<usize>| {
+            let mut line = line.borrow_mut();
+            *line = *line - old + base;
+        };
+        rebase(&self.line);
+        if let Some(matcher) = self.matcher.borrow().as_ref() {
+            rebase(&matcher.line);
+        }
+    }
+    /// Treat `\r\n` as one line terminator when computing columns (the
+    /// default), so the `\n` of a `\r\n` has the column of the `\r`.
+    /// With `false` the `\r` counts as a regular char.
+    ///
+    /// Lines always end at `\n` and the char after it is at column 1.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("a\r\nb");
+    /// sc.bump_bytes(2);
+    /// assert_eq!(sc.position().column, 2);
+    /// sc.set_crlf(false);
+    /// assert_eq!(sc.position().column, 3);
+    /// sc.bump();
+    /// assert_eq!((sc.position().line, sc.position().column), (2, 1));
+    /// ```
+    pub fn set_crlf(&self, v: bool) {
+        *self.crlf.borrow_mut() = v;
+    }
+    /// Advance the column to the next multiple of `n` at a tab, as an
+    /// editor shows it. With the default of 1 a tab counts as one char.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("\tab\tc");
+    /// sc.set_tab_width(4);
+    /// sc.bump();
+    /// assert_eq!(sc.position().column, 5);
+    /// sc.bump_bytes(3);
+    /// assert_eq!(sc.position().column, 9);
+    /// ```
+    pub fn set_tab_width(&self, n: usize) {
+        *self.tab_width.borrow_mut() = n;
+    }
+    /// Set the chars [`Self::match_float`] reads as the decimal point and
+    /// as the grouping separator between digits, `.` and `_` by default.
+    ///
+    /// For input like `1.234,5` use `set_number_format(',', '.')`: the `,`
+    /// then ends the integer part and a `.` between digits is skipped.
+    /// Note that `.` swaps roles, so `3.14` no longer matches as a float:
+    /// it is read as one grouped integer without a fraction.
+    /// # Panics
+    /// Panics if `decimal_point` and `grouping` are the same char.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("1.234,5 3,14");
+    /// sc.set_number_format(',', '.');
+    /// let float = |sc: &Scanny| sc.matcher().match_float(false).finalize(|v| v.is_matched());
+    /// assert_eq!(float(&sc).unwrap().get_byte_pos(), 0..7);
+    /// sc.bump();
+    /// let token = float(&sc).unwrap();
+    /// assert!(token.value);
+    /// assert_eq!(token.text("1.234,5 3,14"), "3,14");
+    /// ```
+    pub fn set_number_format(&self, decimal_point: char, grouping: char) {
+        assert_ne!(
+            decimal_point, grouping,
+            "the decimal point and the grouping separator must differ"
+        );
+        *self.number_format.borrow_mut() = (decimal_point, grouping);
+    }
+    /// Turn the ASCII fast path of [`Self::consume_while`] off, or back on
+    /// when the input is ASCII only.
+    pub(crate) fn set_ascii_fast_path(&self, v: bool) {
+        *self.ascii.borrow_mut() = if v { None } else { Some(false) };
+    }
+    /// Return the current [Position].
+    pub fn position(&self) -> Position {
+        let (byte, line) = if self.matcher.borrow().is_some() {
+            let binding = self.matcher.borrow();
+            let matcher = binding.as_ref().unwrap();
+            (*matcher.byte_pos.borrow(), *matcher.line.borrow())
+        } else {
+            (*self.byte_pos.borrow(), *self.line.borrow())
+        };
+        Position {
+            byte,
+            line,
+            column: self.column_at(byte),
+        }
+    }
+    /// Return the [Position] of the byte offset `byte` in the input,
+    /// independent of the cursor.
+    ///
+    /// Return `None` if `byte` is past the end of the input or not on a
+    /// char boundary.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("ab\ncdé");
+    /// let pos = sc.locate(5).unwrap();
+    /// assert_eq!((pos.line, pos.column), (2, 3));
+    /// assert_eq!(sc.locate(6), None);
+    /// assert_eq!(sc.locate(7).unwrap().column, 4);
+    /// assert_eq!(sc.locate(8), None);
+    /// ```
+    pub fn locate(&self, byte: usize) -> Option<Position> {
+        if !self.whole.is_char_boundary(byte) {
+            return None;
+        }
+        let line = self.line_starts().partition_point(|v| *v <= byte) - 1;
+        Some(Position {
+            byte,
+            line: *self.line_base.borrow() + line,
+            column: self.column_at(byte),
+        })
+    }
+    /// Return the byte offsets of the start of each line, the first one
+    /// is always 0.
+    ///
+    /// The table is built from the whole input on first use and then
+    /// cached, it costs one `usize` per line. [`Self::locate`] and
+    /// [`Self::render_span`] use it for a binary search instead of
+    /// counting newlines.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("ab\n\ncd");
+    /// assert_eq!(&*sc.line_starts(), &[0, 3, 4]);
+    /// ```
+    pub fn line_starts(&self) -> Ref<'_, [usize]> {
+        if self.line_starts.borrow().is_none() {
+            let starts = std::iter::once(0)
+                .chain(self.whole.match_indices('\n').map(|(i, _)| i + 1))
+                .collect();
+            *self.line_starts.borrow_mut() = Some(starts);
+        }
+        Ref::map(self.line_starts.borrow(), |v| v.as_deref().unwrap_or(&[]))
+    }
+    /// Return `true` if the whole input is consumed, up to the active
+    /// matcher if there is one.
+    pub fn at_end(&self) -> bool {
+        self.rest().is_empty()
+    }
+    /// Return the current byte offset, of the active matcher if there is
+    /// one, to get the text consumed from here on with [`Self::since`].
+    pub fn mark(&self) -> usize {
+        self.byte_pos()
+    }
+    /// Return the input from the byte offset `mark` to the current
+    /// position.
+    /// # Panics
+    /// Panics if `mark` is past the current position or not on a char
+    /// boundary.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("key = value");
+    /// let mark = sc.mark();
+    /// while sc.peek().is_some_and(|v| v != '=') {
+    ///     sc.bump();
+    /// }
+    /// assert_eq!(sc.since(mark).trim_end(), "key");
+    /// ```
+    pub fn since(&self, mark: usize) -> &'a str {
+        let current = self.mark();
+        assert!(
+            mark <= current,
+            "mark {mark} is past the current position {current}"
+        );
+        assert!(
+            self.whole.is_char_boundary(mark),
+            "byte {mark} is not a char boundary"
+        );
+        &self.whole[mark..current]
+    }
+    /// Return the number of bytes consumed so far.
+    pub fn bytes_consumed(&self) -> usize {
+        self.byte_pos()
+    }
+    /// Return the number of chars consumed so far.
+    ///
+    /// Unlike [`Self::bytes_consumed`] a multi-byte char counts once.
+    pub fn chars_consumed(&self) -> usize {
+        if self.matcher.borrow().is_some() {
+            *self.matcher.borrow().as_ref().unwrap().char_pos.borrow()
+        } else {
+            *self.char_pos.borrow()
+        }
+    }
+    /// Return the number of bytes left to consume.
+    pub fn remaining_bytes(&self) -> usize {
+        self.rest().len()
+    }
+    /// Return the number of chars left to consume.
+    ///
+    /// The first call counts the chars of the whole input, later calls
+    /// are `O(1)`.
+    pub fn remaining_chars(&self) -> usize {
+        let total = *self
+            .char_count
+            .borrow_mut()
+            .get_or_insert_with(|| self.whole.chars().count());
+        total - self.chars_consumed()
+    }
+    /// Return the byte range matched so far by the active matcher, or `None`
+    /// if there is no matcher.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("abcdef");
+    /// assert_eq!(sc.current_match_span(), None);
+    /// let matched = sc
+    ///     .matcher()
+    ///     .then('a')
+    ///     .then_peek(|v| v.current_match_span().unwrap().len() <= 3)
+    ///     .consume_while(|v| v.is_ascii_alphabetic())
+    ///     .finalize(|v| v.value())
+    ///     .unwrap()
+    ///     .value;
+    /// assert_eq!(matched, "abcdef");
+    /// ```
+    pub fn current_match_span(&self) -> Option<Range<usize>> {
+        let binding = self.matcher.borrow();
+        let matcher = binding.as_ref()?;
+        Some(*self.byte_pos.borrow()..*matcher.byte_pos.borrow())
+    }
+    /// Return the UTF-16 code unit offset, as used by LSP, of the byte
+    /// offset `byte_pos` in the input.
+    ///
+    /// This walks the input from the start, so it is `O(n)`. For many
+    /// lookups cache the offsets of line starts and convert from there.
+    /// # Panics
+    /// Panics if `byte_pos` is past the end of the input or not on a char
+    /// boundary.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let src = "a😀b";
+    /// let sc = Scanny::new(src);
+    /// assert_eq!(sc.utf16_offset(5), 3);
+    /// let b = sc
+    ///     .skeep_while(|v| v != 'b')
+    ///     .matcher()
+    ///     .then('b')
+    ///     .finalize(|_| ())
+    ///     .unwrap();
+    /// assert_eq!(b.utf16_range(src), 3..4);
+    /// ```
+    pub fn utf16_offset(&self, byte_pos: usize) -> usize {
+        pos::utf16_offset(self.whole, byte_pos)
+    }
+    /// Return `true` if the input was consumed since `pos` was taken.
+    ///
+    /// A match chain may finish without consuming anything, use this to
+    /// stop a token loop that would otherwise spin forever.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("12ab");
+    /// let mut numbers = Vec::new();
+    /// loop {
+    ///     let start = sc.position();
+    ///     let num = sc
+    ///         .matcher()
+    ///         .consume_while(char::is_ascii_digit)
+    ///         .finalize(|v| v.value())
+    ///         .unwrap();
+    ///     if !sc.did_advance_since(start) {
+    ///         break;
+    ///     }
+    ///     numbers.push(num.value);
+    /// }
+    /// assert_eq!(numbers, vec!["12"]);
+    /// ```
+    pub fn did_advance_since(&self, pos: Position) -> bool {
+        self.byte_pos() != pos.byte
+    }
+    /// Limit the number of bytes a single match may span.
+    ///
+    /// The limit applies per match, from [`Self::matcher`] to
+    /// [`Self::finalize`]. Once the next char would make the match longer
+    /// than `n` bytes, [`Self::consume_while`] and the `consume_until_*`
+    /// combinators stop and the match fails. The default is unlimited
+    /// (`usize::MAX`).
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("averyveryverylongname");
+    /// sc.set_max_token_bytes(8);
+    /// let token = sc
+    ///     .matcher()
+    ///     .consume_while(char::is_ascii_alphabetic)
+    ///     .finalize(|v| {
+    ///         assert!(v.is_not_matched());
+    ///         v.value()
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(token.value, "averyver");
+    /// ```
+    pub fn set_max_token_bytes(&self, n: usize) {
+        *self.max_token_bytes.borrow_mut() = n;
+    }
+    /// Return `true` if the input is ASCII only, checked once on first use.
+    fn is_ascii(&self) -> bool {
+        *self
+            .ascii
+            .borrow_mut()
+            .get_or_insert_with(|| self.whole.is_ascii())
+    }
+    /// Return `true` if consuming `ch` would make the active match longer
+    /// than the max token bytes.
+    fn exceeds_max_token(&self, ch: char) -> bool {
+        let max = *self.max_token_bytes.borrow();
+        if max == usize::MAX {
+            return false;
+        }
+        self.current_match_span()
+            .is_some_and(|v| v.len() + ch.len_utf8() > max)
+    }
+    /// Call `f` with the name of each combinator and the position at which
+    /// it starts, to find out where a match chain fails.
+    /// # Example
+    /// ```rust
+    /// use std::{cell::RefCell, rc::Rc};
+    /// use scanny::Scanny;
+    ///
+    /// let calls = Rc::new(RefCell::new(Vec::new()));
+    /// let sc = Scanny::new("ab");
+    /// let log = calls.clone();
+    /// sc.set_trace(move |name, pos| log.borrow_mut().push((name.to_string(), pos.byte)));
+    /// sc.matcher().then('a').then('c').then('b');
+    /// sc.clear_trace();
+    /// sc.finalize(|_| {});
+    /// assert_eq!(
+    ///     *calls.borrow(),
+    ///     vec![
+    ///         ("matcher".to_string(), 0),
+    ///         ("then".to_string(), 0),
+    ///         ("then".to_string(), 1),
+    ///         ("then".to_string(), 1),
+    ///     ]
+    /// );
+    /// ```
+    pub fn set_trace<F: Fn(&str, Position) + 'static>(&self, f: F) {
+        *self.trace.borrow_mut() = Some(Rc::new(f));
+    }
+    /// Remove the callback set by [`Self::set_trace`].
+    pub fn clear_trace(&self) {
+        *self.trace.borrow_mut() = None;
+    }
+    fn trace(&self, name: &'static str) {
+        #[cfg(feature = "profiling")]
+        {
+            *self.stats.borrow_mut().combinators.entry(name).or_default() += 1;
+        }
+        let trace = self.trace.borrow().clone();
+        if let Some(f) = trace {
+            f(name, self.position());
+        }
+    }
     /// Creates and stores a new `Matcher` instance in the [Scanny] struct.
+    ///
+    /// Until [`Self::finalize`] every method reading or moving the cursor,
+    /// like [`Self::peek`], [`Self::bump`] or [`Self::position`], works on
+    /// the matcher and the scanner stays at the start of the token. Calling
+    /// `matcher` again while a matcher is active does nothing.
     /// # Example 1
     /// ```rust
     /// use scanny::Scanny;
@@ -133,18 +845,23 @@ impl<'a> Scanny<'a> {
     ///     assert_eq!(first_word, None);
     /// ```
     pub fn matcher(&self) -> &Self {
+        self.trace("matcher");
         if self.matcher.borrow().is_some() {
             return self;
         }
-        let chars = (*self.chars.borrow()).clone();
         let byte_pos = *self.byte_pos.borrow();
+        let char_pos = *self.char_pos.borrow();
         let line = *self.line.borrow();
         let matcher = Matcher {
-            chars: Rc::new(RefCell::new(chars)),
             byte_pos: Rc::new(RefCell::new(byte_pos)),
+            char_pos: Rc::new(RefCell::new(char_pos)),
             line: Rc::new(RefCell::new(line)),
             is_matched: Rc::new(RefCell::new(false)),
             match_next: Rc::new(RefCell::new(true)),
+            snapshot_pos: Rc::new(RefCell::new(byte_pos)),
+            consume_on_match: Rc::new(RefCell::new(true)),
+            consume_on_not_match: Rc::new(RefCell::new(true)),
+            captures: Rc::new(RefCell::new(Vec::new())),
         };
         *self.matcher.borrow_mut() = Some(matcher);
         self
@@ -158,6 +875,16 @@ impl<'a> Scanny<'a> {
         let matcher = binding.as_ref().unwrap();
         *matcher.is_matched.borrow()
     }
+    /// Return `true` if the active match would finalize as
+    /// [`MatchType::All`]. Return `false` without a matcher.
+    pub fn match_succeeded(&self) -> bool {
+        self.matcher.borrow().is_some() && (self.is_matched() || self.next_match())
+    }
+    /// Return `true` if the active match would finalize as
+    /// [`MatchType::Few`]. Return `false` without a matcher.
+    pub fn match_failed(&self) -> bool {
+        self.matcher.borrow().is_some() && !self.match_succeeded()
+    }
     /// Call this method if the token is matched.
     /// # Example
     /// ```rust
@@ -216,6 +943,59 @@ impl<'a> Scanny<'a> {
         *binding.as_mut().unwrap().is_matched.borrow_mut() = true;
         self
     }
+    /// Reset the active match to neither matched nor failed, keeping its
+    /// position, so the chain can go on with a new condition. Does nothing
+    /// without a matcher.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("ab");
+    /// sc.matcher().then('a').then('x');
+    /// assert!(sc.match_failed());
+    /// let token = sc.clear_match_flags().then('b').finalize(|v| v.is_matched());
+    /// assert!(token.unwrap().value);
+    /// ```
+    pub fn clear_match_flags(&self) -> &Self {
+        if let Some(matcher) = self.matcher.borrow().as_ref() {
+            *matcher.is_matched.borrow_mut() = false;
+            *matcher.match_next.borrow_mut() = true;
+        }
+        self
+    }
+    /// Do not consume the input when the active match is finalized as
+    /// [`MatchType::All`], same as calling
+    /// [`MatchType::consume_on_match`] with `false` in `finalize`.
+    pub fn no_consume_on_match(&self) -> &Self {
+        if let Some(matcher) = self.matcher.borrow().as_ref() {
+            *matcher.consume_on_match.borrow_mut() = false;
+        }
+        self
+    }
+    /// Do not consume the input when the active match is finalized as
+    /// [`MatchType::Few`], same as calling
+    /// [`MatchType::consume_on_not_match`] with `false` in `finalize`.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("12x");
+    /// let token = sc
+    ///     .matcher()
+    ///     .no_consume_on_not_match()
+    ///     .consume_while(char::is_ascii_digit)
+    ///     .then('.')
+    ///     .finalize(|v| v.value())
+    ///     .unwrap();
+    /// assert_eq!(token.value, "12");
+    /// assert_eq!(sc.peek(), Some('1'));
+    /// ```
+    pub fn no_consume_on_not_match(&self) -> &Self {
+        if let Some(matcher) = self.matcher.borrow().as_ref() {
+            *matcher.consume_on_not_match.borrow_mut() = false;
+        }
+        self
+    }
     /// Get the next chat without consuming it.
     /// # Example
     /// ```rust
@@ -230,89 +1010,335 @@ impl<'a> Scanny<'a> {
     /// assert_eq!(sc.bump(), None);
     /// ```
     pub fn peek(&self) -> Option<char> {
-        let mut chars = if self.matcher.borrow().is_some() {
-            (*self.matcher.borrow().as_ref().unwrap().chars.borrow()).clone()
-        } else {
-            (*self.chars.borrow()).clone()
-        };
-        chars.next()
+        count!(self, peeks);
+        self.rest().chars().next()
     }
     /// Return the second char without consuming it.
     pub fn peek_second(&self) -> Option<char> {
-        let mut chars = if self.matcher.borrow().is_some() {
-            (*self.matcher.borrow().as_ref().unwrap().chars.borrow()).clone()
-        } else {
-            (*self.chars.borrow()).clone()
-        };
-        chars.next();
-        chars.next()
+        count!(self, peeks);
+        self.rest().chars().nth(1)
     }
     /// Return third char without consuming it.
     pub fn peek_third(&self) -> Option<char> {
-        let mut chars = if self.matcher.borrow().is_some() {
-            (*self.matcher.borrow().as_ref().unwrap().chars.borrow()).clone()
-        } else {
-            (*self.chars.borrow()).clone()
-        };
-        chars.next();
-        chars.next();
-        chars.next()
+        count!(self, peeks);
+        self.rest().chars().nth(2)
     }
     /// Return nth char without consuming it.
     /// Time Complexity: `O(n)`
     pub fn peek_nth(&self, n: usize) -> Option<char> {
-        let mut chars = if self.matcher.borrow().is_some() {
-            (*self.matcher.borrow().as_ref().unwrap().chars.borrow()).clone()
-        } else {
-            (*self.chars.borrow()).clone()
-        };
-        chars.nth(n)
+        count!(self, peeks);
+        self.rest().chars().nth(n)
     }
-    /// Return and consume the next char
-    pub fn bump(&self) -> Option<char> {
-        if self.matcher.borrow().is_some() {
-            let matcher = self.matcher.clone().borrow_mut().clone().unwrap();
-            match matcher.chars.borrow_mut().next() {
-                v @ Some('\n') => {
-                    *matcher.byte_pos.borrow_mut() += 1;
-                    *matcher.line.borrow_mut() += 1;
-                    v
-                }
-                v @ Some(ch) => {
-                    *matcher.byte_pos.borrow_mut() += ch.len_utf8();
-                    v
-                }
-                v @ None => v,
-            }
-        } else {
-            match self.chars.borrow_mut().next() {
-                v @ Some('\n') => {
-                    *self.byte_pos.borrow_mut() += 1;
-                    *self.line.borrow_mut() += 1;
-                    v
-                }
-                v @ Some(ch) => {
-                    *self.byte_pos.borrow_mut() += ch.len_utf8();
-                    v
-                }
-                v @ None => v,
-            }
-        }
+    /// Return the char before the current position, of the active matcher
+    /// if there is one. It is decoded from the input, so no history needs
+    /// to be kept.
+    /// # Example
+    /// ```rust
+    /// # use scanny::Scanny;
+    /// let sc = Scanny::new("é!");
+    /// assert_eq!(sc.peek_back(), None);
+    /// sc.bump();
+    /// assert_eq!(sc.peek_back(), Some('é'));
+    /// ```
+    pub fn peek_back(&self) -> Option<char> {
+        self.whole[..self.byte_pos()].chars().next_back()
     }
-    pub fn skeep_while<F: Fn(char) -> bool>(&self, f: F) -> &Self {
-        if self.is_matched() {
-            return self;
-        }
-        if !self.next_match() {
-            return self;
-        }
-        while self.peek().is_some_and(&f) {
-            self.bump();
-        }
-        self
+    /// Return the nth char without consuming it, with its byte offset from
+    /// the current position.
+    /// Time Complexity: `O(n)`
+    /// # Example
+    /// ```rust
+    /// # use scanny::Scanny;
+    /// let sc = Scanny::new("aé€b");
+    /// assert_eq!(sc.peek_with_offset(0), Some(('a', 0)));
+    /// assert_eq!(sc.peek_with_offset(2), Some(('€', 3)));
+    /// assert_eq!(sc.peek_with_offset(3), Some(('b', 6)));
+    /// assert_eq!(sc.peek_with_offset(4), None);
+    /// ```
+    pub fn peek_with_offset(&self, n: usize) -> Option<(char, usize)> {
+        count!(self, peeks);
+        self.rest().char_indices().nth(n).map(|(i, ch)| (ch, i))
     }
-    /// match the next char, consume on match.
+    /// Return the input up to, but not including, the next `delim` without
+    /// consuming it. Return `None` if `delim` does not occur.
+    /// # Example
+    /// ```rust
+    /// # use scanny::Scanny;
+    /// let sc = Scanny::new("key: value");
+    /// assert_eq!(sc.peek_until(':'), Some("key"));
+    /// assert_eq!(sc.peek_until(';'), None);
+    /// assert_eq!(sc.peek(), Some('k'));
+    /// ```
+    pub fn peek_until(&self, delim: char) -> Option<&'a str> {
+        let rest = self.rest();
+        rest.find(delim).map(|v| &rest[..v])
+    }
+    /// Return the number of chars at the start of the rest of the input for
+    /// which `f` returns true, without consuming them.
+    /// # Example
+    /// ```rust
+    /// # use scanny::Scanny;
+    /// let sc = Scanny::new("ééé!");
+    /// assert_eq!(sc.peek_while_count(|v| v == 'é'), 3);
+    /// assert_eq!(sc.peek(), Some('é'));
+    /// ```
+    pub fn peek_while_count<F: Fn(char) -> bool>(&self, f: F) -> usize {
+        count!(self, peeks);
+        self.rest().chars().take_while(|v| f(*v)).count()
+    }
+    /// Return the rest of the current line, up to but not including the
+    /// next `\n`, without consuming it.
+    /// # Example
+    /// ```rust
+    /// # use scanny::Scanny;
+    /// let sc = Scanny::new("+ added\n- removed");
+    /// assert_eq!(sc.peek_line(), "+ added");
+    /// assert_eq!(sc.peek(), Some('+'));
+    /// ```
+    pub fn peek_line(&self) -> &'a str {
+        let rest = self.rest();
+        rest.find('\n').map_or(rest, |v| &rest[..v])
+    }
+    /// Return and consume the next char.
+    ///
+    /// While a matcher is active (between [`Self::matcher`] and
+    /// [`Self::finalize`]) the char is consumed from the matcher only, the
+    /// scanner itself moves when `finalize` commits the match. Outside of a
+    /// matcher the scanner moves immediately.
+    ///
+    /// `bump` ignores the state of the match chain, use [`Self::match_one`]
+    /// to consume a char only while the chain is still matching.
+    pub fn bump(&self) -> Option<char> {
+        count!(self, bumps);
+        let binding = self.matcher.borrow();
+        let (byte_pos, char_pos, line) = match binding.as_ref() {
+            Some(m) => (&m.byte_pos, &m.char_pos, &m.line),
+            None => (&self.byte_pos, &self.char_pos, &self.line),
+        };
+        let mut rest = self.whole[*byte_pos.borrow()..].char_indices();
+        let (_, ch) = rest.next()?;
+        count!(self, bytes_scanned, rest.offset());
+        *byte_pos.borrow_mut() += rest.offset();
+        *char_pos.borrow_mut() += 1;
+        if ch == '\n' {
+            *line.borrow_mut() += 1;
+        }
+        Some(ch)
+    }
+    /// Like [`Self::bump`], also returning the span of the char, for one
+    /// char tokens like `(` or `;`. A `\n` is on the line it ends.
+    /// # Example
+    /// ```rust
+    /// # use scanny::Scanny;
+    /// let sc = Scanny::new("é;");
+    /// sc.bump();
+    /// let semi = sc.bump_spanned().unwrap();
+    /// assert_eq!((semi.value, semi.get_byte_pos()), (';', 2..3));
+    /// assert_eq!(sc.bump_spanned(), None);
+    /// ```
+    pub fn bump_spanned(&self) -> Option<WithPos<char>> {
+        let start = self.save();
+        let ch = self.bump()?;
+        Some(
+            self.with_pos_since(&start, ch)
+                .set_line_pos(start.line..=start.line),
+        )
+    }
+    /// Consume everything up to `delim`, or to the end of the input if
+    /// `delim` does not occur, and return it. The delimiter is not
+    /// consumed. Like [`Self::bump`] this moves the active matcher if there
+    /// is one.
+    /// # Example
+    /// ```rust
+    /// # use scanny::Scanny;
+    /// let sc = Scanny::new("Host: example.org");
+    /// let name = sc.bump_until(':');
+    /// assert_eq!((name.value, name.get_byte_pos()), ("Host", 0..4));
+    /// sc.bump();
+    /// assert_eq!(sc.skip_hspace().bump_until('\n').value, "example.org");
+    /// assert!(sc.at_end());
+    /// ```
+    pub fn bump_until(&self, delim: char) -> WithPos<&'a str> {
+        let start = self.save();
+        let rest = self.rest();
+        let text = self.bump_bytes(rest.find(delim).unwrap_or(rest.len()));
+        self.with_pos_since(&start, text)
+    }
+    /// Consume the next `n` bytes, or the rest of the input if it is
+    /// shorter, and return them. Like [`Self::bump`] this moves the active
+    /// matcher if there is one.
+    /// # Panics
+    /// Panics if the `n`th byte is not on a char boundary.
+    /// # Example
+    /// ```rust
+    /// # use scanny::Scanny;
+    /// let sc = Scanny::new("héllo\nworld");
+    /// assert_eq!(sc.bump_bytes(7), "héllo\n");
+    /// assert_eq!(sc.position().line, 2);
+    /// assert_eq!(sc.peek(), Some('w'));
+    /// ```
+    pub fn bump_bytes(&self, n: usize) -> &'a str {
+        let mut state = self.save();
+        let rest = self.rest();
+        let n = n.min(rest.len());
+        assert!(
+            rest.is_char_boundary(n),
+            "byte {} is not a char boundary",
+            state.byte_pos + n
+        );
+        let skipped = &rest[..n];
+        state.byte_pos += n;
+        state.char_pos += skipped.chars().count();
+        state.line += skipped.matches('\n').count();
+        count!(self, bytes_scanned, n);
+        self.restore(state);
+        skipped
+    }
+    /// Return and consume the next char as part of the match chain.
+    ///
+    /// Unlike [`Self::bump`], nothing is consumed and `None` is returned if
+    /// the chain has already matched or failed.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("ab");
+    /// assert_eq!(sc.matcher().then('x').match_one(), None);
+    /// sc.finalize(|v| v.consume_on_not_match(false));
+    /// assert_eq!(sc.matcher().then('a').match_one(), Some('b'));
+    /// ```
+    pub fn match_one(&self) -> Option<char> {
+        self.trace("match_one");
+        if self.is_matched() {
+            return None;
+        }
+        if !self.next_match() {
+            return None;
+        }
+        self.bump()
+    }
+    /// Consume `ch` if it is the next char and return whether it was.
+    ///
+    /// Like [`Self::then_optional`] this never fails the match, but tells
+    /// if the optional part was present. Nothing is consumed and `false`
+    /// is returned if the chain has already matched or failed.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("1.5f");
+    /// sc.matcher().match_float(false);
+    /// let single = sc.opt_char('f');
+    /// let token = sc.finalize(|v| v.value()).unwrap();
+    /// assert!(single);
+    /// assert_eq!(token.value, "1.5f");
+    /// ```
+    pub fn opt_char(&self, ch: char) -> bool {
+        self.trace("opt_char");
+        if self.is_matched() || !self.next_match() || self.peek() != Some(ch) {
+            return false;
+        }
+        self.bump();
+        true
+    }
+    /// Consume `s` if the upcoming input starts with it and return whether
+    /// it did, see [`Self::opt_char`].
+    pub fn opt_str(&self, s: &str) -> bool {
+        self.trace("opt_str");
+        if self.is_matched() || !self.next_match() || !self.rest().starts_with(s) {
+            return false;
+        }
+        self.bump_bytes(s.len());
+        true
+    }
+    /// Consume a UTF-8 byte-order mark (`\u{FEFF}`) if it is the next char.
+    /// Usually called once, right after creating the scanner.
+    pub fn skip_bom(&self) -> &Self {
+        if self.peek() == Some('\u{FEFF}') {
+            self.bump();
+        }
+        self
+    }
+    pub fn skeep_while<F: Fn(char) -> bool>(&self, f: F) -> &Self {
+        self.trace("skeep_while");
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        while self.peek().is_some_and(&f) {
+            self.bump();
+        }
+        self
+    }
+    /// Consume a `\` directly followed by `\n` or `\r\n`, so a token can
+    /// go on on the next line as in shell or C preprocessor input. Does
+    /// nothing otherwise.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("ab\\\ncd e");
+    /// let word = sc
+    ///     .matcher()
+    ///     .consume_while(char::is_ascii_alphabetic)
+    ///     .skip_line_continuation()
+    ///     .consume_while(char::is_ascii_alphabetic)
+    ///     .finalize(|v| v.value())
+    ///     .unwrap();
+    /// assert_eq!(word.value, "ab\\\ncd");
+    /// assert_eq!(word.get_line_pos(), 1..=2);
+    /// ```
+    pub fn skip_line_continuation(&self) -> &Self {
+        self.trace("skip_line_continuation");
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        let rest = self.rest();
+        if rest.starts_with("\\\n") {
+            self.bump_bytes(2);
+        } else if rest.starts_with("\\\r\n") {
+            self.bump_bytes(3);
+        }
+        self
+    }
+    /// Skip whitespace other than `\n`, so the end of the line is left for
+    /// the next token.
+    pub fn skip_hspace(&self) -> &Self {
+        self.skeep_while(|v| v.is_whitespace() && v != '\n')
+    }
+    /// match at least one whitespace char other than `\n`, consume on match.
+    pub fn match_hspace(&self) -> &Self {
+        self.then_while(|v| v.is_whitespace() && *v != '\n')
+    }
+    /// match an ASCII digit `0-9`, consume on match.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("a1_");
+    /// let token = sc.matcher().then_alpha().then_digit().finalize(|v| v.value());
+    /// assert_eq!(token.unwrap().value, "a1");
+    /// assert!(sc.matcher().then_alnum().match_failed());
+    /// ```
+    pub fn then_digit(&self) -> &Self {
+        self.match_char(char::is_ascii_digit)
+    }
+    /// match an ASCII letter, consume on match.
+    pub fn then_alpha(&self) -> &Self {
+        self.match_char(char::is_ascii_alphabetic)
+    }
+    /// match an ASCII letter or digit, consume on match.
+    pub fn then_alnum(&self) -> &Self {
+        self.match_char(char::is_ascii_alphanumeric)
+    }
+    /// match the next char, consume on match.
     pub fn match_char<F: Fn(&char) -> bool>(&self, f: F) -> &Self {
+        self.trace("match_char");
         if self.is_matched() {
             return self;
         }
@@ -320,19 +1346,18 @@ impl<'a> Scanny<'a> {
             return self;
         }
         match self.peek() {
-            Some(ch) => {
-                if f(&ch) {
-                    self.bump();
-                    self
-                } else {
-                    self.set_next_match(false);
-                    self
-                }
+            Some(ch) if f(&ch) => {
+                self.bump();
+                self
+            }
+            _ => {
+                self.set_next_match(false);
+                self
             }
-            None => self,
         }
     }
     pub fn match_char_optional<F: Fn(&char) -> bool>(&self, f: F) -> &Self {
+        self.trace("match_char_optional");
         if self.is_matched() {
             return self;
         }
@@ -353,6 +1378,7 @@ impl<'a> Scanny<'a> {
     }
     /// match the next char, consume on match.
     pub fn then(&self, ch: char) -> &Self {
+        self.trace("then");
         if self.is_matched() {
             return self;
         }
@@ -370,7 +1396,44 @@ impl<'a> Scanny<'a> {
             }
         }
     }
+    /// Consume `ch` or panic with the position and the char found instead.
+    ///
+    /// Meant for quick tools where malformed input is a bug, parsers that
+    /// must handle bad input should use [`Self::then`] instead.
+    /// # Panics
+    /// Panics if the next char is not `ch`.
+    /// ```rust,should_panic
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("a\nb=");
+    /// sc.expect_char('a').expect_char('\n').expect_char(':');
+    /// // panics: expected ':' at 2:2, found 'b'
+    /// ```
+    pub fn expect_char(&self, ch: char) -> &Self {
+        self.trace("expect_char");
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        match self.peek() {
+            Some(c) if c == ch => {
+                self.bump();
+                self
+            }
+            found => {
+                let pos = self.position();
+                let found = found.map_or("end of input".to_string(), |v| format!("{v:?}"));
+                panic!(
+                    "expected {ch:?} at {}:{}, found {found}",
+                    pos.line, pos.column
+                );
+            }
+        }
+    }
     pub fn then_optional(&self, ch: char) -> &Self {
+        self.trace("then_optional");
         if self.is_matched() {
             return self;
         }
@@ -385,7 +1448,110 @@ impl<'a> Scanny<'a> {
             _ => self,
         }
     }
+    /// match `n` consecutive `ch`, consume on match.
+    /// Exactly `n` chars are consumed, any further `ch` is left for the
+    /// next combinator.
+    pub fn then_repeat(&self, ch: char, n: usize) -> &Self {
+        self.trace("then_repeat");
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        for _ in 0..n {
+            if self.peek() != Some(ch) {
+                self.set_next_match(false);
+                return self;
+            }
+            self.bump();
+        }
+        self
+    }
+    /// match exactly `n` consecutive chars for which `f` returns true,
+    /// consume on match. Like [`Self::then_repeat`] any further matching
+    /// char is left for the next combinator.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("#ff80c0");
+    /// let color = sc
+    ///     .matcher()
+    ///     .then('#')
+    ///     .match_exact_run(char::is_ascii_hexdigit, 6)
+    ///     .finalize(|v| v.is_matched())
+    ///     .unwrap();
+    /// assert!(color.value);
+    /// ```
+    pub fn match_exact_run<F: Fn(&char) -> bool>(&self, f: F, n: usize) -> &Self {
+        self.trace("match_exact_run");
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        for _ in 0..n {
+            if !self.peek().is_some_and(|v| f(&v)) {
+                self.set_next_match(false);
+                return self;
+            }
+            self.bump();
+        }
+        self
+    }
+    /// match the next char if it falls in `range`, consume on match.
+    pub fn then_range(&self, range: RangeInclusive<char>) -> &Self {
+        self.trace("then_range");
+        self.then_ranges_impl(&[range])
+    }
+    /// match the next char if it falls in any of `ranges`, consume on match.
+    pub fn then_ranges(&self, ranges: &[RangeInclusive<char>]) -> &Self {
+        self.trace("then_ranges");
+        self.then_ranges_impl(ranges)
+    }
+    /// [`Self::then_ranges`] without tracing.
+    fn then_ranges_impl(&self, ranges: &[RangeInclusive<char>]) -> &Self {
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        match self.peek() {
+            Some(c) if ranges.iter().any(|v| v.contains(&c)) => {
+                self.bump();
+                self
+            }
+            _ => {
+                self.set_next_match(false);
+                self
+            }
+        }
+    }
+    /// match the next char if it is in `class`, consume on match.
+    pub fn match_class(&self, class: &CharClass) -> &Self {
+        self.trace("match_class");
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        match self.peek() {
+            Some(c) if class.contains(c) => {
+                self.bump();
+                self
+            }
+            _ => {
+                self.set_next_match(false);
+                self
+            }
+        }
+    }
     pub fn then_any<F: Fn(Option<char>) -> bool>(&self, f: F) -> &Self {
+        self.trace("then_any");
         if self.is_matched() {
             return self;
         }
@@ -402,6 +1568,7 @@ impl<'a> Scanny<'a> {
     }
     /// Conditionally perform actions like `peek` or `bump` based on a predicate.
     pub fn then_peek<F: Fn(Self) -> bool>(&self, f: F) -> &Self {
+        self.trace("then_peek");
         if self.is_matched() {
             return self;
         }
@@ -415,282 +1582,3407 @@ impl<'a> Scanny<'a> {
             self
         }
     }
-    pub fn then_any_optional(&self, chars: &[char]) -> &Self {
+    /// Apply `f` to the next char and consume it if `f` returns `Some`.
+    ///
+    /// This is a query, on `None` nothing is consumed and the match does
+    /// not fail.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("fz");
+    /// assert_eq!(sc.then_map(|v| v.to_digit(16)), Some(15));
+    /// assert_eq!(sc.then_map(|v| v.to_digit(16)), None);
+    /// assert_eq!(sc.peek(), Some('z'));
+    /// ```
+    pub fn then_map<T, F: Fn(char) -> Option<T>>(&self, f: F) -> Option<T> {
+        self.trace("then_map");
+        if self.is_matched() {
+            return None;
+        }
+        if !self.next_match() {
+            return None;
+        }
+        let value = f(self.peek()?)?;
+        self.bump();
+        Some(value)
+    }
+    /// match one of `alts` if present and consume it, otherwise do nothing.
+    /// The longest matching alternative is consumed, so `alts` does not
+    /// need to be sorted.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("42u16");
+    /// let token = sc
+    ///     .matcher()
+    ///     .consume_while(char::is_ascii_digit)
+    ///     .then_any_str_optional(&["u8", "u1", "u16", "i32"])
+    ///     .finalize(|v| v.value())
+    ///     .unwrap();
+    /// assert_eq!(token.value, "42u16");
+    /// ```
+    pub fn then_any_str_optional(&self, alts: &[&str]) -> &Self {
+        self.trace("then_any_str_optional");
         if self.is_matched() {
             return self;
         }
         if !self.next_match() {
             return self;
         }
-        match self.peek() {
-            Some(c) if chars.contains(&c) => {
-                self.bump();
-                self
-            }
-            _ => self,
+        let rest = self.rest();
+        let longest = alts
+            .iter()
+            .filter(|v| rest.starts_with(**v))
+            .map(|v| v.len())
+            .max();
+        if let Some(len) = longest {
+            self.bump_bytes(len);
         }
+        self
     }
-    /// Conditionally perform actions like `peek` or `bump` based on a predicate.
-    /// Bump the next char if the callback fn return true until it return false.
-    pub fn peek_and_consume<F: Fn(Self) -> bool>(&self, f: F) -> &Self {
+    /// match `chars` in order, consume on match. Nothing is consumed if
+    /// any of them differs.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("\\t\\n");
+    /// assert!(sc.matcher().then_sequence(&['\\', 'n']).match_failed());
+    /// sc.finalize(|v| v.consume_on_not_match(false));
+    /// let tab = sc.matcher().then_sequence(&['\\', 't']).finalize(|v| v.is_matched());
+    /// assert!(tab.unwrap().value);
+    /// assert_eq!(sc.peek(), Some('\\'));
+    /// ```
+    pub fn then_sequence(&self, chars: &[char]) -> &Self {
+        self.trace("then_sequence");
         if self.is_matched() {
             return self;
         }
         if !self.next_match() {
             return self;
         }
-        loop {
-            if f(self.clone()) {
-                self.bump();
-            } else {
+        let rest = self.rest();
+        let mut len = 0;
+        for (expected, got) in chars.iter().zip(rest.chars()) {
+            if *expected != got {
                 break;
             }
+            len += got.len_utf8();
+        }
+        if len == chars.iter().map(|v| v.len_utf8()).sum() {
+            self.bump_bytes(len);
+        } else {
+            self.set_next_match(false);
         }
         self
     }
-    /// Bump the next char until callback fn return false.
-    pub fn consume_while<F: Fn(&char) -> bool>(&self, f: F) -> &Self {
+    /// Lookahead: go on only if the upcoming input starts with `s`, without
+    /// consuming it.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("foo::bar");
+    /// let token = sc
+    ///     .matcher()
+    ///     .consume_while(char::is_ascii_alphabetic)
+    ///     .then_if_str("::")
+    ///     .finalize(|v| v.value())
+    ///     .unwrap();
+    /// assert_eq!(token.value, "foo");
+    /// assert_eq!(sc.peek(), Some(':'));
+    /// ```
+    pub fn then_if_str(&self, s: &str) -> &Self {
+        self.trace("then_if_str");
         if self.is_matched() {
             return self;
         }
         if !self.next_match() {
             return self;
         }
-        loop {
-            match self.peek() {
-                Some(ch) if f(&ch) => {
-                    self.bump();
-                }
-                _ => break,
-            }
+        if !self.rest().starts_with(s) {
+            self.set_next_match(false);
         }
         self
     }
-    /// Consume the `Matcher` instance.
+    /// Negative lookahead: go on only if the upcoming input does not start
+    /// with `s`, without consuming it.
+    pub fn then_unless_str(&self, s: &str) -> &Self {
+        self.trace("then_unless_str");
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        if self.rest().starts_with(s) {
+            self.set_next_match(false);
+        }
+        self
+    }
+    /// Match with a hand-written recognizer, consume on match.
+    ///
+    /// `f` gets the rest of the input and returns the length in bytes of
+    /// the token it recognized at its start, or `None` to fail the match.
+    /// # Panics
+    /// Panics if the returned length is not on a char boundary of the rest
+    /// of the input.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("2024-01-05 rest");
+    /// let date = sc
+    ///     .matcher()
+    ///     .custom(|rest| {
+    ///         let ok = rest.len() >= 10 && rest.as_bytes()[4] == b'-' && rest.as_bytes()[7] == b'-';
+    ///         ok.then_some(10)
+    ///     })
+    ///     .finalize(|v| v.value())
+    ///     .unwrap();
+    /// assert_eq!(date.value, "2024-01-05");
+    /// ```
+    pub fn custom<F: Fn(&'a str) -> Option<usize>>(&self, f: F) -> &Self {
+        self.trace("custom");
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        let rest = self.rest();
+        match f(rest) {
+            Some(n) => {
+                assert!(
+                    rest.is_char_boundary(n),
+                    "custom length {n} is not a char boundary of the rest of the input"
+                );
+                self.bump_bytes(n);
+            }
+            None => self.set_next_match(false),
+        }
+        self
+    }
+    /// Lookbehind: go on only if `f` returns true for the char before the
+    /// current position, see [`Self::peek_back`]. `f` gets `None` at the
+    /// start of the input.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// // `/` is a division after a value, otherwise it starts a regex
+    /// let division = |sc: &Scanny| {
+    ///     sc.matcher()
+    ///         .then_if_prev(|v| v.is_some_and(|v| v.is_alphanumeric() || v == ')'))
+    ///         .then('/')
+    ///         .finalize(|v| v.is_matched())
+    ///         .unwrap()
+    ///         .value
+    /// };
+    /// let sc = Scanny::new("a/b");
+    /// sc.bump();
+    /// assert!(division(&sc));
+    /// let sc = Scanny::new("(/b/");
+    /// sc.bump();
+    /// assert!(!division(&sc));
+    /// ```
+    pub fn then_if_prev<F: Fn(Option<char>) -> bool>(&self, f: F) -> &Self {
+        self.trace("then_if_prev");
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        if !f(self.peek_back()) {
+            self.set_next_match(false);
+        }
+        self
+    }
+    /// Negative lookahead: fail if `f` returns true. Anything `f` consumes
+    /// is given back.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("foo(");
+    /// sc.matcher()
+    ///     .consume_while(char::is_ascii_alphabetic)
+    ///     .not(|v| v.peek() == Some('('));
+    /// assert!(sc.finalize(|v| v.is_not_matched()).unwrap().value);
+    /// ```
+    pub fn not<F: Fn(&Self) -> bool>(&self, f: F) -> &Self {
+        self.trace("not");
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        let state = self.save();
+        let found = f(self);
+        self.restore(state);
+        if found {
+            self.set_next_match(false);
+        }
+        self
+    }
+    /// Return `true` if the chain built by `f` matches at the current
+    /// position, without consuming anything.
+    ///
+    /// `f` runs in a fresh matcher which is then dropped. An active
+    /// matcher is left as it was, so this also works inside a chain.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("0x1f");
+    /// assert!(sc.would_match(|sc| sc.match_prefixed_int()));
+    /// assert!(!sc.would_match(|sc| sc.match_float(false)));
+    /// assert_eq!(sc.position().byte, 0);
+    /// ```
+    pub fn would_match<F: Fn(&Self) -> &Self>(&self, f: F) -> bool {
+        let state = self.save();
+        let outer = self.matcher.borrow_mut().take();
+        self.matcher();
+        self.restore(state);
+        f(self);
+        let found = self.match_succeeded();
+        *self.matcher.borrow_mut() = outer;
+        found
+    }
+    /// Run the chain built by `f` and consume what it matched if it
+    /// succeeds, returning the matched text. If it fails nothing is
+    /// consumed and `None` is returned.
+    ///
+    /// Like [`Self::would_match`] the chain runs in a fresh matcher, inside
+    /// an active matcher the match is added to it. If that matcher has
+    /// already failed or finished, `f` is not run and `None` is returned.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("0x1f+");
+    /// assert_eq!(sc.consume_if(|sc| sc.match_float(false)), None);
+    /// let int = sc.consume_if(|sc| sc.match_prefixed_int()).unwrap();
+    /// assert_eq!((int.value, int.get_byte_pos()), ("0x1f", 0..4));
+    /// assert_eq!(sc.peek(), Some('+'));
+    /// ```
+    pub fn consume_if<F: Fn(&Self) -> &Self>(&self, f: F) -> Option<WithPos<&'a str>> {
+        if self.is_matched() || !self.next_match() {
+            return None;
+        }
+        let start = self.save();
+        let outer = self.matcher.borrow_mut().take();
+        self.matcher();
+        self.restore(start.clone());
+        f(self);
+        let found = self.match_succeeded();
+        let end = self.save();
+        *self.matcher.borrow_mut() = outer;
+        if !found {
+            count!(self, backtracks);
+            return None;
+        }
+        let text = &self.whole[start.byte_pos..end.byte_pos];
+        self.restore(end);
+        Some(self.with_pos_since(&start, text))
+    }
+    pub fn then_any_optional(&self, chars: &[char]) -> &Self {
+        self.trace("then_any_optional");
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        match self.peek() {
+            Some(c) if chars.contains(&c) => {
+                self.bump();
+                self
+            }
+            _ => self,
+        }
+    }
+    /// match an integer with an optional `0x`, `0o` or `0b` prefix (any
+    /// case), consume on match. Digits may be separated by `_`.
+    ///
+    /// With a prefix at least one digit of its radix must follow, otherwise
+    /// at least one decimal digit is required.
+    pub fn match_prefixed_int(&self) -> &Self {
+        self.trace("match_prefixed_int");
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        let radix = match (self.peek(), self.peek_second()) {
+            (Some('0'), Some('x' | 'X')) => 16,
+            (Some('0'), Some('o' | 'O')) => 8,
+            (Some('0'), Some('b' | 'B')) => 2,
+            _ => 10,
+        };
+        if radix != 10 {
+            self.bump();
+            self.bump();
+        }
+        let mut digits = 0;
+        while let Some(ch) = self.peek() {
+            if ch.is_digit(radix) {
+                digits += 1;
+            } else if ch != '_' || digits == 0 && radix == 10 {
+                break;
+            }
+            self.bump();
+        }
+        if digits == 0 {
+            self.set_next_match(false);
+        }
+        self
+    }
+    /// match an integer in `radix` where a `_` is only allowed between
+    /// two digits, consume on match.
+    ///
+    /// [`Self::match_prefixed_int`] and [`Self::parse_u64`] accept any
+    /// `_` after the first digit, this fails on `1_`, `1__2` and, like
+    /// them, on `_1`.
+    /// # Panics
+    /// Panics if `radix` is not in `2..=36`.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let int = |src| {
+    ///     Scanny::new(src)
+    ///         .matcher()
+    ///         .match_int_strict(10)
+    ///         .finalize(|v| v.is_matched())
+    ///         .unwrap()
+    ///         .value
+    /// };
+    /// assert!(int("1_000"));
+    /// assert!(!int("1__000"));
+    /// ```
+    pub fn match_int_strict(&self, radix: u32) -> &Self {
+        assert!((2..=36).contains(&radix), "radix {radix} is not in 2..=36");
+        self.trace("match_int_strict");
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        let is_digit = |v: Option<char>| v.is_some_and(|v| v.is_digit(radix));
+        if !is_digit(self.peek()) {
+            self.set_next_match(false);
+            return self;
+        }
+        loop {
+            while is_digit(self.peek()) {
+                self.bump();
+            }
+            if self.peek() != Some('_') {
+                return self;
+            }
+            self.bump();
+            if !is_digit(self.peek()) {
+                self.set_next_match(false);
+                return self;
+            }
+        }
+    }
+    /// match a float, consume on match.
+    ///
+    /// A float is at least one digit, then a `.` followed by any digits,
+    /// like `12.5` or `12.`. Digits after the first may be separated by a
+    /// `_` that is followed by a digit, so `1_000.5` matches but `1_.5`
+    /// does not. Both chars can be changed with
+    /// [`Self::set_number_format`].
+    ///
+    /// With `exponent` an `e` or `E`, an optional sign and at least one
+    /// digit may follow, like `1.5e10`, `1.e+4` or `2E-3`, and the `.` is
+    /// optional when the exponent is present.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("1.2e-3");
+    /// let token = sc.matcher().match_float(true).finalize(|v| v).unwrap();
+    /// assert!(token.is_matched());
+    /// assert_eq!(token.value(), "1.2e-3");
+    /// ```
+    pub fn match_float(&self, exponent: bool) -> &Self {
+        self.trace("match_float");
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        let (point, grouping) = *self.number_format.borrow();
+        let digits = |sc: &Self| {
+            let mut n = 0;
+            while let Some(ch) = sc.peek() {
+                if ch.is_ascii_digit() {
+                    n += 1;
+                } else if ch != grouping
+                    || n == 0
+                    || !sc.peek_second().is_some_and(|v| v.is_ascii_digit())
+                {
+                    break;
+                }
+                sc.bump();
+            }
+            n
+        };
+        if digits(self) == 0 {
+            self.set_next_match(false);
+            return self;
+        }
+        let fraction = self.peek() == Some(point);
+        if fraction {
+            self.bump();
+            if self.peek().is_some_and(|v| v.is_ascii_digit()) {
+                digits(self);
+            }
+        }
+        if exponent && matches!(self.peek(), Some('e' | 'E')) {
+            self.bump();
+            if matches!(self.peek(), Some('+' | '-')) {
+                self.bump();
+            }
+            if digits(self) == 0 {
+                self.set_next_match(false);
+            }
+        } else if !fraction {
+            self.set_next_match(false);
+        }
+        self
+    }
+    /// match a string delimited by `quote` where a doubled `quote` stands
+    /// for a literal one, like `'it''s'` in SQL, consume on match.
+    ///
+    /// Fails if the string is not closed before the end of the input.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("'it''s' x");
+    /// let token = sc
+    ///     .matcher()
+    ///     .match_quoted_doubled('\'')
+    ///     .finalize(|v| v.value())
+    ///     .unwrap();
+    /// assert_eq!(token.value, "'it''s'");
+    /// assert_eq!(sc.peek(), Some(' '));
+    /// ```
+    pub fn match_quoted_doubled(&self, quote: char) -> &Self {
+        self.trace("match_quoted_doubled");
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        if self.peek() != Some(quote) {
+            self.set_next_match(false);
+            return self;
+        }
+        self.bump();
+        loop {
+            match self.bump() {
+                Some(ch) if ch == quote => {
+                    if self.peek() != Some(quote) {
+                        return self;
+                    }
+                    self.bump();
+                }
+                Some(_) => {}
+                None => {
+                    self.set_next_match(false);
+                    return self;
+                }
+            }
+        }
+    }
+    /// Match one of the keywords in `kws` followed by a word boundary,
+    /// consume on match and return its index in `kws`.
+    ///
+    /// Keywords are tried longest first, so `kws` does not need to be sorted.
+    /// Return `None` without consuming if no keyword matches.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let kws = ["in", "int", "if"];
+    /// let sc = Scanny::new("int inx if");
+    /// assert_eq!(sc.match_keyword_set(&kws), Some(1));
+    /// sc.skeep_while(char::is_whitespace);
+    /// assert_eq!(sc.match_keyword_set(&kws), None);
+    /// assert_eq!(sc.peek(), Some('i'));
+    /// ```
+    pub fn match_keyword_set(&self, kws: &[&str]) -> Option<usize> {
+        self.trace("match_keyword_set");
+        if self.is_matched() {
+            return None;
+        }
+        if !self.next_match() {
+            return None;
+        }
+        let rest = self.rest();
+        let mut order: Vec<usize> = (0..kws.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(kws[i].len()));
+        let index = order.into_iter().find(|&i| {
+            let kw = kws[i];
+            !kw.is_empty()
+                && rest.starts_with(kw)
+                && !rest[kw.len()..]
+                    .chars()
+                    .next()
+                    .is_some_and(|v| v.is_alphanumeric() || v == '_')
+        })?;
+        for _ in kws[index].chars() {
+            self.bump();
+        }
+        Some(index)
+    }
+    /// Conditionally perform actions like `peek` or `bump` based on a predicate.
+    /// Bump the next char if the callback fn return true until it return false
+    /// or the input ends.
+    pub fn peek_and_consume<F: Fn(Self) -> bool>(&self, f: F) -> &Self {
+        self.trace("peek_and_consume");
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        while f(self.clone()) && self.bump().is_some() {}
+        self
+    }
+    /// Like [`Self::peek_and_consume`], returning the consumed text with its
+    /// span. It stops at the end of the input even if `f` returns `true`.
+    /// Like [`Self::bump`] this moves the active matcher if there is one.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("ab\\'c' d");
+    /// let body = sc.peek_and_consume_spanned(|v| match v.peek() {
+    ///     Some('\\') => v.bump().is_some(),
+    ///     Some('\'') => false,
+    ///     _ => true,
+    /// });
+    /// assert_eq!((body.value, body.get_byte_pos()), ("ab\\'c", 0..5));
+    /// assert_eq!(sc.peek(), Some('\''));
+    /// ```
+    pub fn peek_and_consume_spanned<F: Fn(&Self) -> bool>(&self, f: F) -> WithPos<&'a str> {
+        let start = self.save();
+        while f(self) && self.bump().is_some() {}
+        let end = self.save();
+        self.with_pos_since(&start, &self.whole[start.byte_pos..end.byte_pos])
+    }
+    /// Bump the next char until callback fn return false.
+    ///
+    /// On ASCII-only input the bytes are scanned directly, without decoding
+    /// chars.
+    pub fn consume_while<F: Fn(&char) -> bool>(&self, f: F) -> &Self {
+        self.trace("consume_while");
+        self.consume_while_impl(f)
+    }
+    /// [`Self::consume_while`] without tracing, for combinators built on it
+    /// that trace under their own name.
+    fn consume_while_impl<F: Fn(&char) -> bool>(&self, f: F) -> &Self {
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        if self.is_ascii() {
+            // every byte is a char, scan the bytes without decoding
+            let rest = self.rest().as_bytes();
+            let mut n = rest.iter().take_while(|v| f(&(**v as char))).count();
+            let max = *self.max_token_bytes.borrow();
+            if let Some(span) = self.current_match_span().filter(|_| max != usize::MAX) {
+                let room = max.saturating_sub(span.len());
+                if n > room {
+                    n = room;
+                    self.set_next_match(false);
+                }
+            }
+            // one byte per char, as if each had been bumped
+            count!(self, bumps, n);
+            self.bump_bytes(n);
+            return self;
+        }
+        loop {
+            match self.peek() {
+                Some(ch) if f(&ch) => {
+                    if self.exceeds_max_token(ch) {
+                        self.set_next_match(false);
+                        break;
+                    }
+                    self.bump();
+                }
+                _ => break,
+            }
+        }
+        self
+    }
+    /// Call `f` with the next char and bump it while `f` returns
+    /// `ControlFlow::Continue`. The char for which `f` returns
+    /// `ControlFlow::Break` is not consumed.
+    /// # Example
+    /// ```rust
+    /// use std::ops::ControlFlow;
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("1234567");
+    /// let mut sum = 0;
+    /// sc.consume_each(|v| {
+    ///     sum += v.to_digit(10).unwrap();
+    ///     if sum > 5 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+    /// });
+    /// assert_eq!(sc.peek(), Some('3'));
+    /// ```
+    pub fn consume_each<F: FnMut(char) -> ControlFlow<()>>(&self, mut f: F) -> &Self {
+        self.trace("consume_each");
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        while let Some(ch) = self.peek() {
+            if f(ch).is_break() {
+                break;
+            }
+            self.bump();
+        }
+        self
+    }
+    /// Bump the next char while `f` returns true, at least one char must
+    /// match. Where [`Self::consume_while`] is `*`, this is `+`.
+    pub fn then_while<F: Fn(&char) -> bool>(&self, f: F) -> &Self {
+        self.trace("then_while");
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        match self.peek() {
+            Some(ch) if f(&ch) => {
+                self.bump();
+                self.consume_while_impl(f)
+            }
+            _ => {
+                self.set_next_match(false);
+                self
+            }
+        }
+    }
+    /// Bump the next char while `f` returns true, if `guard` returns true.
+    /// If `guard` returns false the match fails without consuming.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("42abc");
+    /// let matched = sc
+    ///     .matcher()
+    ///     .consume_while_if(|v| v.peek().is_some_and(|v| v.is_ascii_digit()), char::is_ascii_digit)
+    ///     .finalize(|v| v.value())
+    ///     .unwrap()
+    ///     .value;
+    /// assert_eq!(matched, "42");
+    /// ```
+    pub fn consume_while_if<P, F>(&self, guard: P, f: F) -> &Self
+    where
+        P: Fn(&Self) -> bool,
+        F: Fn(&char) -> bool,
+    {
+        self.trace("consume_while_if");
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        if !guard(self) {
+            self.set_next_match(false);
+            return self;
+        }
+        self.consume_while_impl(f)
+    }
+    /// Bump the next char while it is in `class`.
+    pub fn consume_class(&self, class: &CharClass) -> &Self {
+        self.trace("consume_class");
+        self.consume_while_impl(|v| class.contains(*v))
+    }
+    /// Bump the next char until it is one of `delims` or the input ends.
+    /// The delimiter is not consumed.
+    pub fn consume_until_any(&self, delims: &[char]) -> &Self {
+        self.trace("consume_until_any");
+        self.consume_while_impl(|v| !delims.contains(v))
+    }
+    /// Bump the next char until the input starts with one of `delims` or
+    /// ends. The delimiter is not consumed.
+    pub fn consume_until_any_str(&self, delims: &[&str]) -> &Self {
+        self.trace("consume_until_any_str");
+        self.consume_until_any_str_impl(delims)
+    }
+    /// [`Self::consume_until_any_str`] without tracing.
+    fn consume_until_any_str_impl(&self, delims: &[&str]) -> &Self {
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() || delims.iter().any(|v| !v.is_empty() && rest.starts_with(v)) {
+                break;
+            }
+            if self.exceeds_max_token(rest.chars().next().unwrap()) {
+                self.set_next_match(false);
+                break;
+            }
+            self.bump();
+        }
+        self
+    }
+    /// Bump the rest of the current line, leaving the `\n` (or `\r\n`, see
+    /// [`Self::set_crlf`]) for the next token.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("// note\r\nx");
+    /// let comment = sc
+    ///     .matcher()
+    ///     .then('/')
+    ///     .then('/')
+    ///     .consume_to_eol()
+    ///     .finalize(|v| v.value())
+    ///     .unwrap();
+    /// assert_eq!(comment.value, "// note");
+    /// assert_eq!(sc.peek(), Some('\r'));
+    /// ```
+    pub fn consume_to_eol(&self) -> &Self {
+        self.trace("consume_to_eol");
+        if *self.crlf.borrow() {
+            self.consume_until_any_str_impl(&["\r\n", "\n"])
+        } else {
+            self.consume_while_impl(|v| *v != '\n')
+        }
+    }
+    /// Bump the next char while it falls in any of `ranges`.
+    pub fn consume_while_in(&self, ranges: &[RangeInclusive<char>]) -> &Self {
+        self.trace("consume_while_in");
+        self.consume_while_impl(|v| ranges.iter().any(|r| r.contains(v)))
+    }
+    /// Apply `f` to the part matched since the last snapshot, or since
+    /// [`Self::matcher`], and start a new snapshot from the current
+    /// position. The match goes on. Without a matcher `f` gets `""`.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("12.50");
+    /// sc.matcher().consume_while(char::is_ascii_digit);
+    /// let int = sc.snapshot(|v| v.to_string());
+    /// sc.then('.').snapshot(|_| ());
+    /// sc.consume_while(char::is_ascii_digit);
+    /// let frac = sc.snapshot(|v| v.to_string());
+    /// let all = sc.finalize(|v| v.value()).unwrap().value;
+    /// assert_eq!((int.as_str(), frac.as_str(), all), ("12", "50", "12.50"));
+    /// ```
+    pub fn snapshot<T, F: Fn(&str) -> T>(&self, f: F) -> T {
+        let binding = self.matcher.borrow();
+        let Some(matcher) = binding.as_ref() else {
+            return f("");
+        };
+        let start = *matcher.snapshot_pos.borrow();
+        let end = *matcher.byte_pos.borrow();
+        *matcher.snapshot_pos.borrow_mut() = end;
+        f(&self.whole[start..end])
+    }
+    /// Consume the `Matcher` instance.
+    ///
+    /// The token spans from the scanner position, which does not move while
+    /// the matcher is active, to the matcher position. Every method moving
+    /// the cursor, including [`Self::bump`], moves the matcher instead of
+    /// the scanner while there is one, so the scanner can not get ahead of
+    /// the matcher.
     pub fn finalize<T, F: Fn(MatchType<'a>) -> T>(&self, f: F) -> Option<WithPos<T>> {
+        self.trace("finalize");
         let matcher = self.matcher.borrow_mut().take()?;
         let byte_pos = *self.byte_pos.borrow()..*matcher.byte_pos.borrow();
+        let char_pos = *self.char_pos.borrow()..*matcher.char_pos.borrow();
         let line_pos = *self.line.borrow()..=*matcher.line.borrow();
+        debug_assert!(byte_pos.start <= byte_pos.end);
+        debug_assert!(self.whole.is_char_boundary(byte_pos.start));
+        debug_assert!(self.whole.is_char_boundary(byte_pos.end));
         let matched = self.whole.get(byte_pos.clone()).unwrap();
-        let consume_on_match = Rc::new(RefCell::new(true));
-        let consume_on_not_match = Rc::new(RefCell::new(true));
-        let got = f(if self.is_matched() || *matcher.match_next.borrow() {
+        let consume_on_match = matcher.consume_on_match.clone();
+        let consume_on_not_match = matcher.consume_on_not_match.clone();
+        // the matcher is taken, `self.is_matched()` would always be false
+        let is_matched = *matcher.is_matched.borrow() || *matcher.match_next.borrow();
+        let got = f(if is_matched {
             MatchType::All(matched, consume_on_match.clone())
         } else {
             MatchType::Few(matched, consume_on_not_match.clone())
         });
-        if self.is_matched() || *matcher.match_next.borrow() {
-            if *consume_on_match.borrow() {
-                *self.chars.borrow_mut() = matcher.chars.borrow().clone();
-                *self.byte_pos.borrow_mut() = *matcher.byte_pos.borrow();
-                *self.line.borrow_mut() = *matcher.line.borrow();
+        if is_matched {
+            if *consume_on_match.borrow() {
+                *self.byte_pos.borrow_mut() = *matcher.byte_pos.borrow();
+                *self.char_pos.borrow_mut() = *matcher.char_pos.borrow();
+                *self.line.borrow_mut() = *matcher.line.borrow();
+            }
+        } else if *consume_on_not_match.borrow() {
+            *self.byte_pos.borrow_mut() = *matcher.byte_pos.borrow();
+            *self.char_pos.borrow_mut() = *matcher.char_pos.borrow();
+            *self.line.borrow_mut() = *matcher.line.borrow();
+        }
+        count!(
+            self,
+            backtracks,
+            (*self.byte_pos.borrow() < byte_pos.end) as usize
+        );
+        *self.last_span.borrow_mut() = Some(Span::new(byte_pos.clone(), line_pos.clone()));
+        Some(
+            WithPos::new(got)
+                .set_byte_pos(byte_pos)
+                .set_line_pos(line_pos)
+                .set_char_pos(char_pos),
+        )
+    }
+    /// Run the chain built by `f` and record the input it matches as
+    /// `name`, to be read back in [`Self::finalize_with_captures`].
+    ///
+    /// Nothing is recorded if the match fails or there is no matcher.
+    pub fn capture<F: Fn(&Self) -> &Self>(&self, name: &'static str, f: F) -> &Self {
+        self.trace("capture");
+        if self.is_matched() {
+            return self;
+        }
+        if !self.next_match() {
+            return self;
+        }
+        let start = self.byte_pos();
+        f(self);
+        if !self.match_succeeded() {
+            return self;
+        }
+        let end = self.byte_pos();
+        if let Some(matcher) = self.matcher.borrow().as_ref() {
+            matcher.captures.borrow_mut().push((name, start..end));
+        }
+        self
+    }
+    /// Like [`Self::finalize`], `f` also gets the parts recorded with
+    /// [`Self::capture`].
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("2024-01-15");
+    /// let date = sc
+    ///     .matcher()
+    ///     .capture("year", |sc| sc.match_exact_run(char::is_ascii_digit, 4))
+    ///     .then('-')
+    ///     .capture("month", |sc| sc.match_exact_run(char::is_ascii_digit, 2))
+    ///     .then('-')
+    ///     .capture("day", |sc| sc.match_exact_run(char::is_ascii_digit, 2))
+    ///     .finalize_with_captures(|_, caps| {
+    ///         let get = |name| caps.get(name).unwrap().parse::<u32>().unwrap();
+    ///         (get("year"), get("month"), get("day"))
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(date.value, (2024, 1, 15));
+    /// ```
+    pub fn finalize_with_captures<T, F>(&self, f: F) -> Option<WithPos<T>>
+    where
+        F: Fn(MatchType<'a>, &Captures<'a>) -> T,
+    {
+        let spans = self.matcher.borrow().as_ref()?.captures.take();
+        let captures = Captures::new(self.whole, spans);
+        self.finalize(|v| f(v, &captures))
+    }
+    /// Consume the `Matcher` instance and return `Ok` with the value `ok`
+    /// builds from the matched text on [`MatchType::All`], or `Err` with the
+    /// partial match on [`MatchType::Few`].
+    ///
+    /// The partial match is always consumed, so a loop reporting errors
+    /// goes on after it. Without a matcher an empty `Err` at the current
+    /// position is returned.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("12.x");
+    /// let num = sc
+    ///     .matcher()
+    ///     .match_float(false)
+    ///     .then_peek(|v| !v.peek().is_some_and(|v| v.is_alphanumeric()))
+    ///     .finalize_or_error(|v| v.parse::<f64>().unwrap());
+    /// let err = num.unwrap_err();
+    /// assert_eq!((err.value, err.get_byte_pos()), ("12.", 0..3));
+    /// assert_eq!(sc.peek(), Some('x'));
+    /// ```
+    pub fn finalize_or_error<T, F>(&self, ok: F) -> Result<WithPos<T>, WithPos<&'a str>>
+    where
+        F: FnOnce(&'a str) -> T,
+    {
+        let Some(token) = self.finalize(|v| {
+            v.consume_on_not_match(true);
+            (v.is_matched(), v.value())
+        }) else {
+            let pos = self.position();
+            return Err(self.spanned(pos, ""));
+        };
+        match token.value {
+            (true, text) => Ok(token.with_value(ok(text))),
+            (false, text) => Err(token.with_value(text)),
+        }
+    }
+    /// Return the [Span] of the token produced by the last call to
+    /// [`Self::finalize`], whether it matched or not.
+    ///
+    /// Comparing it with the start of the next token tells if they are
+    /// adjacent, like `a.b` against `a .b`.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("a .b");
+    /// assert_eq!(sc.last_span(), None);
+    /// sc.matcher().then('a').finalize(|_| ());
+    /// sc.skeep_while(char::is_whitespace);
+    /// let adjacent = sc.last_span().unwrap().get_byte_pos().end == sc.position().byte;
+    /// assert!(!adjacent);
+    /// ```
+    pub fn last_span(&self) -> Option<Span> {
+        self.last_span.borrow().clone()
+    }
+    /// Wrap `value` with the positions from `start`, taken earlier with
+    /// [`Self::position`], to the current position.
+    ///
+    /// This is the counterpart of [`Self::finalize`] for hand-written code
+    /// that does not use a matcher.
+    /// # Panics
+    /// Panics if `start` is past the current position.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("  [a, b]");
+    /// sc.skeep_while(char::is_whitespace);
+    /// let start = sc.position();
+    /// let mut items = Vec::new();
+    /// sc.bump();
+    /// while let Some(ch) = sc.bump() {
+    ///     match ch {
+    ///         ']' => break,
+    ///         'a'..='z' => items.push(ch),
+    ///         _ => {}
+    ///     }
+    /// }
+    /// let list = sc.spanned(start, items);
+    /// assert_eq!(list.value, vec!['a', 'b']);
+    /// assert_eq!(list.get_byte_pos(), 2..8);
+    /// ```
+    pub fn spanned<T>(&self, start: Position, value: T) -> WithPos<T> {
+        let end = self.position();
+        assert!(
+            start.byte <= end.byte,
+            "start {} is past the current position {}",
+            start.byte,
+            end.byte
+        );
+        let char_end = self.chars_consumed();
+        let char_start = char_end - self.whole[start.byte..end.byte].chars().count();
+        WithPos::new(value)
+            .set_byte_pos(start.byte..end.byte)
+            .set_line_pos(start.line..=end.line)
+            .set_char_pos(char_start..char_end)
+    }
+    /// Consume the input up to, but not including, the next char in `sync`
+    /// or the end of the input, and return it.
+    ///
+    /// Used after a failed match to skip the bad input up to a point where
+    /// scanning can go on, e.g. the next `;`, reporting the skipped part.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("@#! ; ok");
+    /// let error = sc.recover_to(&[';', '\n']);
+    /// assert_eq!(error.value, "@#! ");
+    /// assert_eq!(error.get_byte_pos(), 0..4);
+    /// assert_eq!(sc.peek(), Some(';'));
+    /// ```
+    pub fn recover_to(&self, sync: &[char]) -> WithPos<&'a str> {
+        let start = self.save();
+        while self.peek().is_some_and(|v| !sync.contains(&v)) {
+            self.bump();
+        }
+        let skipped = &self.whole[start.byte_pos..self.byte_pos()];
+        self.with_pos_since(&start, skipped)
+    }
+    /// Match the `{XXXX}` part of a `\u{XXXX}` escape, with the scanner
+    /// just past the `\u`, and return the char it encodes.
+    ///
+    /// 1 to 6 hex digits are allowed and the value must be a valid char
+    /// (not a surrogate nor above `10FFFF`). Return `None` without
+    /// consuming if the escape is malformed.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new(r"\u{1F600}!");
+    /// sc.bump();
+    /// sc.bump();
+    /// let ch = sc.match_unicode_escape().unwrap();
+    /// assert_eq!(ch.value, '😀');
+    /// assert_eq!(ch.get_byte_pos(), 2..9);
+    /// assert_eq!(sc.peek(), Some('!'));
+    /// ```
+    pub fn match_unicode_escape(&self) -> Option<WithPos<char>> {
+        let start = self.save();
+        let ch = self.unicode_escape();
+        match ch {
+            Some(ch) => Some(self.with_pos_since(&start, ch)),
+            None => {
+                self.restore(start);
+                None
+            }
+        }
+    }
+    fn unicode_escape(&self) -> Option<char> {
+        if self.bump()? != '{' {
+            return None;
+        }
+        let mut code = 0;
+        let mut digits = 0;
+        while let Some(d) = self.peek().and_then(|v| v.to_digit(16)) {
+            if digits == 6 {
+                return None;
+            }
+            code = code * 16 + d;
+            digits += 1;
+            self.bump();
+        }
+        if digits == 0 || self.bump()? != '}' {
+            return None;
+        }
+        char::from_u32(code)
+    }
+    /// Match a char literal like `'a'`, `'\n'` or `'\u{41}'` and return the
+    /// char it encodes.
+    ///
+    /// The literal is delimited by `quote` and `escape` starts one of the
+    /// escapes `n`, `r`, `t`, `0`, `u{...}`, `quote` or `escape` itself.
+    /// Return `None` without consuming if the literal is empty,
+    /// unterminated or holds more than one char.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new(r"'\u{41}' '\'' ''");
+    /// let ch = sc.match_char_literal('\'', '\\').unwrap();
+    /// assert_eq!(ch.value, 'A');
+    /// assert_eq!(ch.get_byte_pos(), 0..8);
+    /// sc.bump();
+    /// assert_eq!(sc.match_char_literal('\'', '\\').unwrap().value, '\'');
+    /// sc.bump();
+    /// assert_eq!(sc.match_char_literal('\'', '\\'), None);
+    /// assert_eq!(sc.peek(), Some('\''));
+    /// ```
+    pub fn match_char_literal(&self, quote: char, escape: char) -> Option<WithPos<char>> {
+        let start = self.save();
+        let ch = self.char_literal(quote, escape);
+        match ch {
+            Some(ch) => Some(self.with_pos_since(&start, ch)),
+            None => {
+                self.restore(start);
+                None
+            }
+        }
+    }
+    fn char_literal(&self, quote: char, escape: char) -> Option<char> {
+        if self.bump()? != quote {
+            return None;
+        }
+        let ch = match self.bump()? {
+            ch if ch == quote => return None,
+            ch if ch == escape => match self.bump()? {
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                '0' => '\0',
+                'u' => self.unicode_escape()?,
+                ch if ch == quote || ch == escape => ch,
+                _ => return None,
+            },
+            ch => ch,
+        };
+        (self.bump()? == quote).then_some(ch)
+    }
+    /// Match a string opened by one of `quotes` and closed by the same
+    /// char, and return that quote char with the span of the whole string.
+    ///
+    /// `escape` makes the following char part of the string, so it can hold
+    /// its own quote. Return `None` without consuming if the next char is
+    /// not one of `quotes` or the string is not closed.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new(r#"`a"b` "c\"d""#);
+    /// let s = sc.match_any_quoted(&['"', '\'', '`'], '\\').unwrap();
+    /// assert_eq!((s.value, s.get_byte_pos()), ('`', 0..5));
+    /// sc.bump();
+    /// let s = sc.match_any_quoted(&['"', '\'', '`'], '\\').unwrap();
+    /// assert_eq!((s.value, s.get_byte_pos()), ('"', 6..12));
+    /// ```
+    pub fn match_any_quoted(&self, quotes: &[char], escape: char) -> Option<WithPos<char>> {
+        let quote = self.peek().filter(|v| quotes.contains(v))?;
+        let start = self.save();
+        self.bump();
+        loop {
+            match self.bump() {
+                Some(ch) if ch == quote => return Some(self.with_pos_since(&start, quote)),
+                Some(ch) if ch == escape => {
+                    self.bump();
+                }
+                Some(_) => {}
+                None => {
+                    self.restore(start);
+                    return None;
+                }
+            }
+        }
+    }
+    /// Consume a block comment from `open` to the first `close`, like
+    /// `/* ... */`, and return it with its span. The lines it crosses are
+    /// counted, so later tokens get their right line.
+    ///
+    /// Return `None` without consuming if the input does not start with
+    /// `open` or the comment is not closed.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("/* a\n b */ x");
+    /// let comment = sc.consume_block_comment("/*", "*/").unwrap();
+    /// assert_eq!(comment.value, "/* a\n b */");
+    /// assert_eq!(comment.get_line_pos(), 1..=2);
+    /// ```
+    pub fn consume_block_comment(&self, open: &str, close: &str) -> Option<WithPos<&'a str>> {
+        let rest = self.rest();
+        let body = rest.strip_prefix(open)?;
+        let len = open.len() + body.find(close)? + close.len();
+        let start = self.save();
+        let text = self.bump_bytes(len);
+        Some(self.with_pos_since(&start, text))
+    }
+    /// Match a run of digits in `radix` and parse it to a `u64`.
+    ///
+    /// Digits after the first may be separated by `_`. Return `None`
+    /// without consuming if there is no digit or the value overflows.
+    /// # Panics
+    /// Panics if `radix` is not in `2..=36`.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("1_000 ff");
+    /// let num = sc.parse_u64(10).unwrap();
+    /// assert_eq!(num.value, 1000);
+    /// assert_eq!(num.get_byte_pos(), 0..5);
+    /// sc.bump();
+    /// assert_eq!(sc.parse_u64(16).unwrap().value, 255);
+    /// ```
+    pub fn parse_u64(&self, radix: u32) -> Option<WithPos<u64>> {
+        assert!((2..=36).contains(&radix), "radix {radix} is not in 2..=36");
+        let start = self.save();
+        match self.digits_u64(radix) {
+            Some(n) => Some(self.with_pos_since(&start, n)),
+            None => {
+                self.restore(start);
+                None
+            }
+        }
+    }
+    /// Like [`Self::parse_u64`] with an optional leading `+` or `-`.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("-9223372036854775808");
+    /// let num = sc.parse_i64(10).unwrap();
+    /// assert_eq!(num.value, i64::MIN);
+    /// assert_eq!(num.get_byte_pos(), 0..20);
+    /// ```
+    pub fn parse_i64(&self, radix: u32) -> Option<WithPos<i64>> {
+        assert!((2..=36).contains(&radix), "radix {radix} is not in 2..=36");
+        let start = self.save();
+        let neg = match self.peek() {
+            Some(sign @ ('+' | '-')) => {
+                self.bump();
+                sign == '-'
+            }
+            _ => false,
+        };
+        let n = self.digits_u64(radix).and_then(|n| {
+            if neg {
+                0i64.checked_sub_unsigned(n)
+            } else {
+                i64::try_from(n).ok()
+            }
+        });
+        match n {
+            Some(n) => Some(self.with_pos_since(&start, n)),
+            None => {
+                self.restore(start);
+                None
+            }
+        }
+    }
+    fn digits_u64(&self, radix: u32) -> Option<u64> {
+        let mut n = self.bump()?.to_digit(radix)? as u64;
+        while let Some(ch) = self.peek() {
+            if let Some(d) = ch.to_digit(radix) {
+                n = n.checked_mul(radix as u64)?.checked_add(d as u64)?;
+            } else if ch != '_' {
+                break;
+            }
+            self.bump();
+        }
+        Some(n)
+    }
+    /// Extend a finalized token with the input `f` consumes.
+    ///
+    /// `f` gets the scanner at the current position and the value of
+    /// `prev`, runs its own match and returns the new value. The returned
+    /// token starts where `prev` starts and ends at the new position.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("12.75;");
+    /// let int = sc
+    ///     .matcher()
+    ///     .consume_while(char::is_ascii_digit)
+    ///     .finalize(|v| v.value().parse::<f64>().unwrap())
+    ///     .unwrap();
+    /// let num = if sc.peek() == Some('.') {
+    ///     sc.extend_match(int, |sc, int| {
+    ///         let frac = sc
+    ///             .matcher()
+    ///             .then('.')
+    ///             .consume_while(char::is_ascii_digit)
+    ///             .finalize(|v| v.value().parse::<f64>().unwrap())
+    ///             .unwrap();
+    ///         int + frac.value
+    ///     })
+    /// } else {
+    ///     int
+    /// };
+    /// assert_eq!(num.value, 12.75);
+    /// assert_eq!(num.get_byte_pos(), 0..5);
+    /// ```
+    pub fn extend_match<T, F>(&self, prev: WithPos<T>, f: F) -> WithPos<T>
+    where
+        F: FnOnce(&Self, T) -> T,
+    {
+        let byte_pos = prev.get_byte_pos();
+        let line_pos = prev.get_line_pos();
+        let char_pos = prev.get_char_pos();
+        let token = prev.map(|v| f(self, v));
+        let end = self.save();
+        token
+            .set_byte_pos(byte_pos.start..end.byte_pos.max(byte_pos.end))
+            .set_line_pos(*line_pos.start()..=end.line.max(*line_pos.end()))
+            .set_char_pos(char_pos.start..end.char_pos.max(char_pos.end))
+    }
+    /// Run every alternative from the current position and keep the token
+    /// of the one that consumed the most input, the first one wins a tie.
+    /// The input consumed by the other alternatives is given back.
+    ///
+    /// An alternative should return `None` when it does not match. Call
+    /// this outside of a matcher, as the alternatives run their own.
+    /// # Example
+    /// ```rust
+    /// use scanny::{Scanny, WithPos};
+    ///
+    /// fn op(sc: &Scanny, op: &'static str) -> Option<WithPos<&'static str>> {
+    ///     sc.matcher();
+    ///     op.chars().for_each(|c| {
+    ///         sc.then(c);
+    ///     });
+    ///     let token = sc.finalize(|v| {
+    ///         v.consume_on_not_match(false);
+    ///         v.is_matched()
+    ///     })?;
+    ///     token.value.then(|| {
+    ///         WithPos::new(op)
+    ///             .set_byte_pos(token.get_byte_pos())
+    ///             .set_line_pos(token.get_line_pos())
+    ///     })
+    /// }
+    /// let sc = Scanny::new("<<= 1");
+    /// let alts: [&dyn Fn(&Scanny) -> Option<WithPos<&'static str>>; 3] =
+    ///     [&|sc| op(sc, "<"), &|sc| op(sc, "<<"), &|sc| op(sc, "<<=")];
+    /// assert_eq!(sc.longest(&alts).unwrap().value, "<<=");
+    /// assert_eq!(sc.peek(), Some(' '));
+    /// ```
+    pub fn longest<T, F>(&self, alts: &[F]) -> Option<WithPos<T>>
+    where
+        F: Fn(&Self) -> Option<WithPos<T>>,
+    {
+        let start = self.save();
+        let last_span = self.last_span();
+        let mut best: Option<(WithPos<T>, State)> = None;
+        for alt in alts {
+            self.restore(start.clone());
+            let Some(token) = alt(self) else {
+                continue;
+            };
+            let end = self.save();
+            if best.as_ref().is_none_or(|(_, v)| end.byte_pos > v.byte_pos) {
+                best = Some((token, end));
+            }
+        }
+        match best {
+            Some((token, end)) => {
+                self.restore(end);
+                *self.last_span.borrow_mut() = Some(token.span());
+                Some(token)
+            }
+            None => {
+                self.restore(start);
+                *self.last_span.borrow_mut() = last_span;
+                None
+            }
+        }
+    }
+    /// Match `item (sep item)*`, like the path `a.b.c`, and return the
+    /// items with a span covering all of them.
+    ///
+    /// `item` returns `None` when it does not match. If no item follows a
+    /// `sep`, the `sep` and anything `item` consumed are given back, so
+    /// `a.b.` gives `a` and `b` and leaves the last `.`. If the first item
+    /// does not match the list is empty.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("foo.bar.baz.= 1");
+    /// let path = sc.dotted('.', |sc| sc.consume_if(|sc| sc.then_while(char::is_ascii_alphabetic)));
+    /// let names: Vec<_> = path.value.iter().map(|v| v.value).collect();
+    /// assert_eq!(names, vec!["foo", "bar", "baz"]);
+    /// assert_eq!(path.get_byte_pos(), 0..11);
+    /// assert_eq!(sc.peek(), Some('.'));
+    /// ```
+    pub fn dotted<T, F>(&self, sep: char, item: F) -> WithPos<Vec<WithPos<T>>>
+    where
+        F: Fn(&Self) -> Option<WithPos<T>>,
+    {
+        let start = self.save();
+        let mut items = Vec::new();
+        let mut end = self.save();
+        while items.is_empty() || self.peek() == Some(sep) {
+            if !items.is_empty() {
+                self.bump();
+            }
+            match item(self) {
+                Some(v) => {
+                    items.push(v);
+                    end = self.save();
+                }
+                None => break,
+            }
+        }
+        self.restore(end);
+        self.with_pos_since(&start, items)
+    }
+    /// Call `f` until the input is exhausted or `f` returns `None`, and
+    /// collect the tokens into a `Vec` with capacity for `hint` tokens.
+    ///
+    /// To avoid an infinite loop the collection also stops if a call to `f`
+    /// does not consume anything, that token is dropped.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("12 345 6x 7");
+    /// let numbers = sc.collect_tokens(
+    ///     |sc| {
+    ///         sc.skeep_while(char::is_whitespace)
+    ///             .matcher()
+    ///             .consume_while(char::is_ascii_digit)
+    ///             .finalize(|v| v.value())
+    ///     },
+    ///     8,
+    /// );
+    /// let numbers: Vec<&str> = numbers.into_iter().map(|v| v.value).collect();
+    /// assert_eq!(numbers, vec!["12", "345", "6"]);
+    /// assert_eq!(sc.peek(), Some('x'));
+    /// ```
+    pub fn collect_tokens<T, F>(&self, mut f: F, hint: usize) -> Vec<WithPos<T>>
+    where
+        F: FnMut(&Self) -> Option<WithPos<T>>,
+    {
+        let mut tokens = Vec::with_capacity(hint);
+        while self.peek().is_some() {
+            let start = self.byte_pos();
+            let Some(token) = f(self) else {
+                break;
+            };
+            if self.byte_pos() == start {
+                break;
+            }
+            tokens.push(token);
+        }
+        tokens
+    }
+    /// Iterate the results of calling `f` until the input is exhausted or
+    /// `f` returns `None`, for lexers that report errors.
+    ///
+    /// Collecting into `Result<Vec<_>, _>` stops at the first error. As
+    /// with [`Self::collect_tokens`] the iteration also stops if a call to
+    /// `f` does not consume anything, a token from that call is dropped but
+    /// an error is still yielded.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("12 34 x5");
+    /// let numbers: Result<Vec<_>, _> = sc
+    ///     .try_tokens(|sc| {
+    ///         sc.skeep_while(char::is_whitespace);
+    ///         let pos = sc.position().byte;
+    ///         let token = sc
+    ///             .matcher()
+    ///             .consume_while(char::is_ascii_digit)
+    ///             .finalize(|v| v.value())?;
+    ///         Some(match token.value {
+    ///             "" => Err(pos),
+    ///             _ => Ok(token),
+    ///         })
+    ///     })
+    ///     .collect();
+    /// assert_eq!(numbers, Err(6));
+    /// ```
+    pub fn try_tokens<T, E, F>(&self, mut f: F) -> impl Iterator<Item = Result<WithPos<T>, E>>
+    where
+        F: FnMut(&Self) -> Option<Result<WithPos<T>, E>>,
+    {
+        let mut done = false;
+        std::iter::from_fn(move || {
+            if done || self.peek().is_none() {
+                return None;
+            }
+            let start = self.byte_pos();
+            let Some(result) = f(self) else {
+                done = true;
+                return None;
+            };
+            if self.byte_pos() == start {
+                done = true;
+                return result.err().map(Err);
+            }
+            Some(result)
+        })
+    }
+    /// Check that the scanner and the active matcher point to a char
+    /// boundary of the input and agree with their char and line counts.
+    #[cfg(test)]
+    pub(crate) fn assert_invariants(&self) {
+        let check = |byte_pos: usize, char_pos: usize, line: usize| {
+            assert!(byte_pos <= self.whole.len());
+            assert!(self.whole.is_char_boundary(byte_pos));
+            let before = &self.whole[..byte_pos];
+            assert_eq!(char_pos, before.chars().count());
+            let lines = before.matches('\n').count();
+            assert_eq!(line, lines + *self.line_base.borrow());
+        };
+        check(
+            *self.byte_pos.borrow(),
+            *self.char_pos.borrow(),
+            *self.line.borrow(),
+        );
+        if let Some(matcher) = self.matcher.borrow().as_ref() {
+            check(
+                *matcher.byte_pos.borrow(),
+                *matcher.char_pos.borrow(),
+                *matcher.line.borrow(),
+            );
+            assert!(*self.byte_pos.borrow() <= *matcher.byte_pos.borrow());
+        }
+    }
+    /// Render the source line(s) covered by `span` with a `^` underline
+    /// beneath the spanned columns. A span crossing several lines is
+    /// underlined on each line.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("let a = @;");
+    /// let token = sc
+    ///     .skeep_while(|v| v != '@')
+    ///     .matcher()
+    ///     .then('@')
+    ///     .finalize(|v| v.value())
+    ///     .unwrap();
+    /// assert_eq!(sc.render_span(&token.span()), "1 | let a = @;\n  |         ^");
+    /// ```
+    pub fn render_span(&self, span: &Span) -> String {
+        let byte_pos = span.get_byte_pos();
+        let line_pos = span.get_line_pos();
+        let start = byte_pos.start.min(self.whole.len());
+        let end = byte_pos.end.clamp(start, self.whole.len());
+        debug_assert!(self.whole.is_char_boundary(start));
+        debug_assert!(self.whole.is_char_boundary(end));
+        let width = line_pos.end().to_string().len();
+        let mut line_start = {
+            let starts = self.line_starts();
+            starts[starts.partition_point(|v| *v <= start) - 1]
+        };
+        let mut line_no = *line_pos.start();
+        let mut out = Vec::new();
+        loop {
+            let line_end = self.whole[line_start..]
+                .find('\n')
+                .map_or(self.whole.len(), |v| line_start + v);
+            let line = &self.whole[line_start..line_end];
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            let from = start.max(line_start);
+            let to = end.min(line_start + line.len()).max(from);
+            let pad: String = self.whole[line_start..from]
+                .chars()
+                .map(|v| if v == '\t' { '\t' } else { ' ' })
+                .collect();
+            let mut carets = self.whole[from..to].chars().count();
+            if carets == 0 && from == start {
+                carets = 1;
+            }
+            out.push(format!("{line_no:>width$} | {line}").trim_end().to_string());
+            out.push(
+                format!("{:width$} | {pad}{}", "", "^".repeat(carets))
+                    .trim_end()
+                    .to_string(),
+            );
+            if end <= line_end + 1 || line_end == self.whole.len() {
+                break;
+            }
+            line_start = line_end + 1;
+            line_no += 1;
+        }
+        out.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::ControlFlow;
+
+    use super::Scanny;
+    use crate::{CharClass, Span, WithPos};
+
+    #[test]
+    fn test_bump() {
+        let sc = Scanny::new("abcd");
+        assert_eq!(sc.bump(), Some('a'));
+        assert_eq!(sc.bump(), Some('b'));
+        assert_eq!(sc.bump(), Some('c'));
+        assert_eq!(sc.bump(), Some('d'));
+        assert_eq!(sc.bump(), None);
+
+        let sc = Scanny::new("abcd");
+        sc.matcher();
+        assert_eq!(sc.bump(), Some('a'));
+        assert_eq!(sc.bump(), Some('b'));
+        assert_eq!(sc.bump(), Some('c'));
+        assert_eq!(sc.bump(), Some('d'));
+        assert_eq!(sc.bump(), None);
+        sc.finalize(|_| {});
+        assert_eq!(sc.bump(), None);
+
+        let sc = Scanny::new("abcd");
+        sc.matcher();
+        assert_eq!(sc.bump(), Some('a'));
+        assert_eq!(sc.bump(), Some('b'));
+        assert_eq!(sc.bump(), Some('c'));
+        assert_eq!(sc.bump(), Some('d'));
+        assert_eq!(sc.bump(), None);
+        sc.finalize(|m| {
+            m.consume_on_match(false);
+        });
+        assert_eq!(sc.bump(), Some('a'));
+        assert_eq!(sc.bump(), Some('b'));
+        assert_eq!(sc.bump(), Some('c'));
+        assert_eq!(sc.bump(), Some('d'));
+        assert_eq!(sc.bump(), None);
+
+        let sc = Scanny::new("abcd");
+        sc.bump(); // consume 'a'
+        sc.bump(); // consume 'b'
+        sc.matcher();
+        assert_eq!(sc.bump(), Some('c'));
+        assert_eq!(sc.bump(), Some('d'));
+        assert_eq!(sc.bump(), None);
+        sc.finalize(|m| {
+            m.consume_on_match(false);
+        });
+        assert_eq!(sc.bump(), Some('c'));
+        assert_eq!(sc.bump(), Some('d'));
+        assert_eq!(sc.bump(), None);
+    }
+    #[test]
+    fn test_peek() {
+        let sc = Scanny::new("abcd");
+        assert_eq!(sc.peek(), Some('a'));
+        assert_eq!(sc.peek(), Some('a'));
+        assert_eq!(sc.bump(), Some('a'));
+        assert_eq!(sc.peek(), Some('b'));
+        assert_eq!(sc.peek(), Some('b'));
+        assert_eq!(sc.bump(), Some('b'));
+        assert_eq!(sc.peek(), Some('c'));
+        assert_eq!(sc.peek(), Some('c'));
+        assert_eq!(sc.bump(), Some('c'));
+        assert_eq!(sc.peek(), Some('d'));
+        assert_eq!(sc.peek(), Some('d'));
+        assert_eq!(sc.bump(), Some('d'));
+        assert_eq!(sc.peek(), None);
+        assert_eq!(sc.bump(), None);
+
+        let sc = Scanny::new("abcd");
+        sc.bump();
+        sc.matcher();
+        assert_eq!(sc.peek(), Some('b'));
+        assert_eq!(sc.bump(), Some('b'));
+        sc.finalize(|_| {});
+        assert_eq!(sc.peek(), Some('c'));
+        assert_eq!(sc.bump(), Some('c'));
+        sc.matcher();
+        assert_eq!(sc.peek(), Some('d'));
+        assert_eq!(sc.bump(), Some('d'));
+        sc.finalize(|_| {});
+        assert_eq!(sc.peek(), None);
+        assert_eq!(sc.bump(), None);
+        sc.matcher();
+        assert_eq!(sc.peek(), None);
+        assert_eq!(sc.bump(), None);
+        sc.finalize(|_| {});
+        assert_eq!(sc.peek(), None);
+        assert_eq!(sc.bump(), None);
+
+        let sc = Scanny::new("abcd");
+        assert_eq!(sc.peek(), Some('a'));
+        assert_eq!(sc.peek_second(), Some('b'));
+        assert_eq!(sc.peek_third(), Some('c'));
+        assert_eq!(sc.peek_nth(3), Some('d'));
+        assert_eq!(sc.peek_nth(4), None);
+        assert_eq!(sc.bump(), Some('a'));
+
+        let sc = Scanny::new("abcd");
+        sc.bump(); // a
+        sc.matcher();
+        sc.bump(); // b
+        sc.bump(); // c
+        sc.finalize(|m| m.consume_on_match(false));
+        assert_eq!(sc.bump(), Some('b'));
+        assert_eq!(sc.bump(), Some('c'));
+        assert_eq!(sc.bump(), Some('d'));
+    }
+    #[test]
+    fn test_skeep_while() {
+        let sc = Scanny::new("    1234abc   ");
+        sc.skeep_while(char::is_whitespace);
+        assert_eq!(sc.bump(), Some('1'));
+        sc.skeep_while(|v| v.is_ascii_digit());
+        assert_eq!(sc.bump(), Some('a'));
+
+        let sc = Scanny::new("    1234abc   ");
+        sc.matcher();
+        sc.skeep_while(char::is_whitespace);
+        assert_eq!(sc.bump(), Some('1'));
+        sc.skeep_while(|v| v.is_ascii_digit());
+        assert_eq!(sc.bump(), Some('a'));
+        sc.finalize(|m| {
+            m.consume_on_match(false);
+            m.consume_on_not_match(false);
+        });
+        sc.skeep_while(char::is_whitespace);
+        assert_eq!(sc.bump(), Some('1'));
+    }
+    #[test]
+    fn test_match_char() {
+        let sc = Scanny::new("1234abc");
+        sc.match_char(|v| *v == '1');
+        assert_eq!(sc.bump(), Some('2'));
+
+        let sc = Scanny::new("1234abc");
+        sc.match_char(|v| *v == '2');
+        assert_eq!(sc.bump(), Some('1'));
+
+        let sc = Scanny::new("1234abc");
+        sc.matcher()
+            .match_char(|v| *v == '1')
+            .match_char(|v| *v == '2');
+        sc.bump();
+        sc.bump();
+        sc.match_char(|v| *v == 'a').match_char(|v| *v == 'b');
+        let matched = sc.finalize(|m| {
+            assert!(m.is_matched());
+            assert!(!m.is_not_matched());
+            m.value()
+        });
+        assert_eq!(matched.unwrap().value, "1234ab");
+        assert_eq!(sc.bump(), Some('c'));
+        assert_eq!(sc.bump(), None);
+
+        let sc = Scanny::new("1234abc");
+        sc.matcher()
+            .match_char(|v| *v == '1')
+            .match_char(|v| *v == '2');
+        sc.bump();
+        sc.bump();
+        sc.match_char(|v| *v == 'b').match_char(|v| *v == 'b');
+        let matched = sc.finalize(|m| {
+            assert!(!m.is_matched());
+            assert!(m.is_not_matched());
+            m.value()
+        });
+        assert_eq!(matched.unwrap().value, "1234");
+        assert_eq!(sc.bump(), Some('a'));
+    }
+    #[test]
+    fn test_peek_and_consume() {
+        let sc = Scanny::new(r"    'ab\' cd''hello world'   ");
+        sc.skeep_while(char::is_whitespace);
+        let matched = sc
+            .matcher()
+            .then('\'')
+            .peek_and_consume(|v| match v.peek() {
+                Some('\\') => {
+                    v.bump();
+                    true
+                }
+                Some('\'') => false,
+                _ => true,
+            })
+            .then('\'')
+            .finalize(|v| v.value())
+            .unwrap()
+            .value;
+        assert_eq!(matched, r"'ab\' cd'");
+        assert_eq!(sc.bump(), Some('\''));
+        assert_eq!(sc.bump(), Some('h'));
+    }
+    #[test]
+    fn test_render_span() {
+        let sc = Scanny::new("let a = 5;\nlet b = \"foo\n  bar\";\n");
+        assert_eq!(
+            sc.render_span(&Span::new(4..5, 1..=1)),
+            "1 | let a = 5;\n  |     ^"
+        );
+        assert_eq!(
+            sc.render_span(&Span::new(19..30, 2..=3)),
+            "2 | let b = \"foo\n  |         ^^^^\n3 |   bar\";\n  | ^^^^^^"
+        );
+        assert_eq!(
+            sc.render_span(&Span::new(10..10, 1..=1)),
+            "1 | let a = 5;\n  |           ^"
+        );
+
+        let sc = Scanny::new("\tx = @");
+        assert_eq!(
+            sc.render_span(&Span::new(5..6, 1..=1)),
+            "1 | \tx = @\n  | \t    ^"
+        );
+    }
+    #[test]
+    fn test_then_repeat() {
+        let sc = Scanny::new(r#""""doc"""""#);
+        let matched = sc
+            .matcher()
+            .then_repeat('"', 3)
+            .consume_while(|v| *v != '"')
+            .then_repeat('"', 3)
+            .finalize(|v| {
+                assert!(v.is_matched());
+                v.value()
+            })
+            .unwrap()
+            .value;
+        assert_eq!(matched, r#""""doc""""#);
+        assert_eq!(sc.bump(), Some('"'));
+
+        let sc = Scanny::new(r#"""doc"#);
+        let matched = sc
+            .matcher()
+            .then_repeat('"', 3)
+            .finalize(|v| {
+                assert!(v.is_not_matched());
+                v.consume_on_not_match(false);
+                v.value()
+            })
+            .unwrap()
+            .value;
+        assert_eq!(matched, r#""""#);
+        assert_eq!(sc.bump(), Some('"'));
+    }
+    #[test]
+    fn test_match_keyword_set() {
+        let kws = ["fn", "for", "format", "f"];
+        let sc = Scanny::new("format for_each fn f(");
+        assert_eq!(sc.match_keyword_set(&kws), Some(2));
+        sc.skeep_while(char::is_whitespace);
+        assert_eq!(sc.match_keyword_set(&kws), None);
+        assert_eq!(sc.peek(), Some('f'));
+        sc.skeep_while(|v| !v.is_whitespace());
+        sc.skeep_while(char::is_whitespace);
+        sc.matcher();
+        assert_eq!(sc.match_keyword_set(&kws), Some(0));
+        sc.finalize(|_| {});
+        sc.skeep_while(char::is_whitespace);
+        assert_eq!(sc.match_keyword_set(&kws), Some(3));
+        assert_eq!(sc.bump(), Some('('));
+    }
+    #[test]
+    fn test_then_range() {
+        let sc = Scanny::new("x7f;");
+        let matched = sc
+            .matcher()
+            .then_range('a'..='z')
+            .then_range('0'..='9')
+            .then_ranges(&['0'..='9', 'a'..='f'])
+            .finalize(|v| {
+                assert!(v.is_matched());
+                v.value()
+            })
+            .unwrap()
+            .value;
+        assert_eq!(matched, "x7f");
+
+        let sc = Scanny::new("xg");
+        let matched = sc
+            .matcher()
+            .then_range('a'..='z')
+            .then_ranges(&['0'..='9', 'a'..='f'])
+            .finalize(|v| {
+                assert!(v.is_not_matched());
+                v.value()
+            })
+            .unwrap()
+            .value;
+        assert_eq!(matched, "x");
+        assert_eq!(sc.bump(), Some('g'));
+    }
+    #[test]
+    fn test_consume_while_in() {
+        let hex = ['0'..='9', 'a'..='f', 'A'..='F'];
+        let sc = Scanny::new("0x1fA9g");
+        let matched = sc
+            .matcher()
+            .then('0')
+            .then('x')
+            .consume_while_in(&hex)
+            .finalize(|v| v.value())
+            .unwrap()
+            .value;
+        assert_eq!(matched, "0x1fA9");
+        assert_eq!(sc.bump(), Some('g'));
+
+        let sc = Scanny::new("0x1f");
+        sc.matcher().then('1').consume_while_in(&hex);
+        assert_eq!(sc.peek(), Some('0'));
+    }
+    #[test]
+    fn test_position() {
+        let sc = Scanny::new("ab\ncé\nd");
+        assert_eq!(sc.position().byte, 0);
+        assert_eq!(sc.position().line, 1);
+        assert_eq!(sc.position().column, 1);
+        let start = sc.position();
+        assert!(!sc.did_advance_since(start));
+        sc.skeep_while(|v| v != 'é');
+        sc.matcher();
+        sc.bump();
+        let pos = sc.position();
+        assert_eq!((pos.byte, pos.line, pos.column), (6, 2, 3));
+        sc.finalize(|m| m.consume_on_match(false));
+        let pos = sc.position();
+        assert_eq!((pos.byte, pos.line, pos.column), (4, 2, 2));
+        assert!(sc.did_advance_since(start));
+    }
+    #[test]
+    fn test_peek_until() {
+        let sc = Scanny::new("naïve,rest");
+        assert_eq!(sc.peek_until(','), Some("naïve"));
+        sc.matcher().bump();
+        assert_eq!(sc.peek_until(','), Some("aïve"));
+        assert_eq!(sc.peek_until('n'), None);
+        sc.assert_invariants();
+        sc.finalize(|m| m.consume_on_match(false));
+        sc.assert_invariants();
+        assert_eq!(sc.peek_until('ï'), Some("na"));
+        assert_eq!(sc.peek(), Some('n'));
+    }
+    #[test]
+    fn test_consumed() {
+        let sc = Scanny::new("aé\n€b");
+        sc.bump();
+        sc.bump();
+        assert_eq!(sc.bytes_consumed(), 3);
+        assert_eq!(sc.chars_consumed(), 2);
+        sc.matcher();
+        sc.bump();
+        sc.bump();
+        assert_eq!(sc.bytes_consumed(), 7);
+        assert_eq!(sc.chars_consumed(), 4);
+        sc.finalize(|m| m.consume_on_match(false));
+        assert_eq!(sc.bytes_consumed(), 3);
+        assert_eq!(sc.chars_consumed(), 2);
+        sc.assert_invariants();
+        sc.matcher().bump();
+        sc.assert_invariants();
+        sc.finalize(|_| {});
+        sc.assert_invariants();
+        assert_eq!(sc.bytes_consumed(), 4);
+        assert_eq!(sc.chars_consumed(), 3);
+    }
+    #[test]
+    #[should_panic(expected = "expected ';' at 2:3, found end of input")]
+    fn test_expect_char() {
+        let sc = Scanny::new("a\nbc");
+        sc.expect_char('a').expect_char('\n');
+        sc.matcher().expect_char('b').expect_char('c');
+        sc.finalize(|_| {});
+        sc.expect_char(';');
+    }
+    #[test]
+    fn test_consume_until_any() {
+        let sc = Scanny::new("foo(a, b)");
+        sc.skeep_while(|v| v != '(');
+        sc.bump();
+        let args: Vec<&str> = (0..2)
+            .map(|_| {
+                let arg = sc
+                    .skeep_while(char::is_whitespace)
+                    .matcher()
+                    .consume_until_any(&[',', ')'])
+                    .finalize(|v| v.value())
+                    .unwrap()
+                    .value;
+                sc.bump();
+                arg
+            })
+            .collect();
+        assert_eq!(args, vec!["a", "b"]);
+        assert_eq!(sc.bump(), None);
+
+        let sc = Scanny::new("a = 1 -- note\nb");
+        let matched = sc
+            .matcher()
+            .consume_until_any_str(&["--", "//"])
+            .finalize(|v| v.value())
+            .unwrap()
+            .value;
+        assert_eq!(matched, "a = 1 ");
+        assert_eq!(sc.peek(), Some('-'));
+
+        let sc = Scanny::new("a - b");
+        sc.consume_until_any_str(&["--", ""]);
+        assert_eq!(sc.peek(), None);
+    }
+    #[test]
+    fn test_consume_while_if() {
+        let sc = Scanny::new("abc123");
+        let matched = sc
+            .matcher()
+            .consume_while_if(
+                |v| v.peek().is_some_and(|v| v.is_ascii_digit()),
+                char::is_ascii_digit,
+            )
+            .finalize(|v| {
+                assert!(v.is_not_matched());
+                v.consume_on_not_match(false);
+                v.value()
+            })
+            .unwrap()
+            .value;
+        assert_eq!(matched, "");
+        assert_eq!(sc.peek(), Some('a'));
+    }
+    #[test]
+    fn test_current_match_span() {
+        let sc = Scanny::new("  abc");
+        sc.skeep_while(char::is_whitespace);
+        sc.matcher();
+        assert_eq!(sc.current_match_span(), Some(2..2));
+        sc.bump();
+        sc.bump();
+        assert_eq!(sc.current_match_span(), Some(2..4));
+        sc.finalize(|_| {});
+        assert_eq!(sc.current_match_span(), None);
+    }
+    #[test]
+    fn test_skip_bom() {
+        let sc = Scanny::new("\u{FEFF}let");
+        sc.skip_bom();
+        assert_eq!(sc.bytes_consumed(), 3);
+        assert_eq!(sc.peek(), Some('l'));
+        sc.skip_bom();
+        assert_eq!(sc.bytes_consumed(), 3);
+
+        let sc = Scanny::new("let\u{FEFF}");
+        sc.skip_bom();
+        assert_eq!(sc.bytes_consumed(), 0);
+    }
+    #[test]
+    fn test_max_token_bytes() {
+        let sc = Scanny::new("ab€de fg");
+        sc.set_max_token_bytes(4);
+        let token = sc
+            .matcher()
+            .consume_until_any(&[' '])
+            .finalize(|v| {
+                assert!(v.is_not_matched());
+                v.consume_on_not_match(false);
+                v.value()
+            })
+            .unwrap();
+        assert_eq!(token.value, "ab");
+        sc.set_max_token_bytes(5);
+        let token = sc
+            .matcher()
+            .consume_until_any_str(&[" "])
+            .finalize(|v| {
+                assert!(v.is_not_matched());
+                v.value()
+            })
+            .unwrap();
+        assert_eq!(token.value, "ab€");
+
+        // the limit is per match and does not apply without a matcher
+        sc.set_max_token_bytes(1);
+        sc.consume_while(|v| *v != ' ');
+        assert_eq!(sc.bump(), Some(' '));
+        let token = sc
+            .matcher()
+            .consume_while(char::is_ascii_alphabetic)
+            .finalize(|v| v.value())
+            .unwrap();
+        assert_eq!(token.value, "f");
+        sc.set_max_token_bytes(usize::MAX);
+        let token = sc
+            .matcher()
+            .consume_while(char::is_ascii_alphabetic)
+            .finalize(|v| v.value())
+            .unwrap();
+        assert_eq!(token.value, "g");
+    }
+    #[test]
+    fn test_peek_line() {
+        let sc = Scanny::new("[core]\nname = x");
+        assert_eq!(sc.peek_line(), "[core]");
+        sc.matcher().consume_while(|v| *v != '\n').then('\n');
+        assert_eq!(sc.peek_line(), "name = x");
+        sc.finalize(|_| {});
+        assert_eq!(sc.peek_line(), "name = x");
+        sc.skeep_while(|_| true);
+        assert_eq!(sc.peek_line(), "");
+    }
+    #[test]
+    fn test_consume_each() {
+        let sc = Scanny::new("ab\ncd;e");
+        let mut seen = String::new();
+        let token = sc
+            .matcher()
+            .consume_each(|v| {
+                if v == ';' {
+                    return ControlFlow::Break(());
+                }
+                seen.push(v);
+                ControlFlow::Continue(())
+            })
+            .finalize(|v| v.value())
+            .unwrap();
+        assert_eq!(seen, "ab\ncd");
+        assert_eq!(token.value, "ab\ncd");
+        assert_eq!(token.get_line_pos(), 1..=2);
+        assert_eq!(sc.peek(), Some(';'));
+    }
+    #[test]
+    fn test_longest() {
+        fn op(sc: &Scanny, op: &'static str) -> Option<WithPos<&'static str>> {
+            sc.matcher();
+            for c in op.chars() {
+                sc.then(c);
             }
-        } else if *consume_on_not_match.borrow() {
-            *self.chars.borrow_mut() = matcher.chars.borrow().clone();
-            *self.byte_pos.borrow_mut() = *matcher.byte_pos.borrow();
-            *self.line.borrow_mut() = *matcher.line.borrow();
+            let token = sc.finalize(|v| {
+                v.consume_on_not_match(false);
+                v.is_matched()
+            })?;
+            token.value.then(|| {
+                WithPos::new(op)
+                    .set_byte_pos(token.get_byte_pos())
+                    .set_line_pos(token.get_line_pos())
+            })
         }
-        Some(
-            WithPos::new(got)
-                .set_byte_pos(byte_pos)
-                .set_line_pos(line_pos),
-        )
+        type Alt = dyn Fn(&Scanny) -> Option<WithPos<&'static str>>;
+        let alts: [&Alt; 4] = [
+            &|sc| op(sc, "<"),
+            &|sc| op(sc, "<<"),
+            &|sc| op(sc, "<<="),
+            &|sc| op(sc, "<="),
+        ];
+        let sc = Scanny::new("<<=<<<=x");
+        let token = sc.longest(&alts).unwrap();
+        assert_eq!(token.value, "<<=");
+        assert_eq!(token.get_byte_pos(), 0..3);
+        assert_eq!(sc.longest(&alts).unwrap().value, "<<");
+        assert_eq!(sc.longest(&alts).unwrap().value, "<=");
+        assert_eq!(sc.longest(&alts), None);
+        assert_eq!(sc.peek(), Some('x'));
+        assert_eq!(sc.bytes_consumed(), 7);
+        assert_eq!(sc.chars_consumed(), 7);
+    }
+    #[test]
+    fn test_char_class() {
+        let ident_start = CharClass::new().ascii_alpha().ch('_');
+        let ident = ident_start.clone().ascii_digit();
+        let sc = Scanny::new("_foo1 9bar");
+        let matched = sc
+            .matcher()
+            .match_class(&ident_start)
+            .consume_class(&ident)
+            .finalize(|v| {
+                assert!(v.is_matched());
+                v.value()
+            })
+            .unwrap()
+            .value;
+        assert_eq!(matched, "_foo1");
+        sc.skeep_while(char::is_whitespace);
+        let matched = sc
+            .matcher()
+            .match_class(&ident_start)
+            .consume_class(&ident)
+            .finalize(|v| {
+                assert!(v.is_not_matched());
+                v.value()
+            })
+            .unwrap()
+            .value;
+        assert_eq!(matched, "");
+    }
+    #[test]
+    fn test_bump_bytes() {
+        let sc = Scanny::new("a\n€\nbc");
+        sc.matcher();
+        assert_eq!(sc.bump_bytes(5), "a\n€");
+        assert_eq!(sc.position().line, 2);
+        sc.assert_invariants();
+        let token = sc.finalize(|v| v.value()).unwrap();
+        assert_eq!(token.value, "a\n€");
+        assert_eq!(token.get_line_pos(), 1..=2);
+        assert_eq!(sc.chars_consumed(), 3);
+        assert_eq!(sc.bump_bytes(10), "\nbc");
+        assert_eq!(sc.position().line, 3);
+        assert_eq!(sc.bump_bytes(1), "");
+        sc.assert_invariants();
+    }
+    #[test]
+    #[should_panic(expected = "byte 3 is not a char boundary")]
+    fn test_bump_bytes_not_boundary() {
+        let sc = Scanny::new("a\n€");
+        sc.bump_bytes(3);
+    }
+    #[test]
+    fn test_not() {
+        fn ident_not_call<'a>(sc: &Scanny<'a>) -> (bool, &'a str) {
+            sc.matcher()
+                .consume_while(char::is_ascii_alphabetic)
+                .not(|v| v.bump() == Some('('))
+                .finalize(|v| {
+                    v.consume_on_not_match(false);
+                    (v.is_matched(), v.value())
+                })
+                .unwrap()
+                .value
+        }
+        let sc = Scanny::new("foo(");
+        assert_eq!(ident_not_call(&sc), (false, "foo"));
+        assert_eq!(sc.peek(), Some('f'));
+
+        let sc = Scanny::new("foo (");
+        assert_eq!(ident_not_call(&sc), (true, "foo"));
+        assert_eq!(sc.peek(), Some(' '));
+    }
+    #[test]
+    fn test_crlf_columns() {
+        let sc = Scanny::new("a\r\nb\rc\r\n");
+        sc.skeep_while(|v| v != 'b');
+        let pos = sc.position();
+        assert_eq!((pos.line, pos.column), (2, 1));
+        sc.skeep_while(|v| v != 'c');
+        assert_eq!(sc.position().column, 3);
+        sc.bump();
+        assert_eq!(sc.position().column, 4);
+        sc.bump();
+        assert_eq!(sc.position().column, 4);
+        sc.set_crlf(false);
+        assert_eq!(sc.position().column, 5);
+        sc.bump();
+        let pos = sc.position();
+        assert_eq!((pos.line, pos.column), (3, 1));
+    }
+    #[test]
+    fn test_char_pos() {
+        let sc = Scanny::new("αβ γδε");
+        let first = sc
+            .matcher()
+            .consume_while(|v| !v.is_whitespace())
+            .finalize(|v| v.value())
+            .unwrap();
+        assert_eq!(first.get_byte_pos(), 0..4);
+        assert_eq!(first.get_char_pos(), 0..2);
+        let second = sc
+            .skeep_while(char::is_whitespace)
+            .matcher()
+            .consume_while(|v| !v.is_whitespace())
+            .finalize(|v| v.value())
+            .unwrap();
+        assert_eq!(second.get_byte_pos(), 5..11);
+        assert_eq!(second.get_char_pos(), 3..6);
+    }
+    #[test]
+    fn test_recover_to() {
+        let sc = Scanny::new("let a = 1;\nlet 9b = 2;\nlet c = 3;");
+        let mut names = Vec::new();
+        let mut errors = Vec::new();
+        while sc.skeep_while(char::is_whitespace).peek().is_some() {
+            let name = sc
+                .matcher()
+                .then('l')
+                .then('e')
+                .then('t')
+                .then(' ')
+                .match_char(char::is_ascii_alphabetic)
+                .then(' ')
+                .finalize(|v| {
+                    v.consume_on_not_match(false);
+                    v.is_matched().then(|| v.value())
+                })
+                .unwrap();
+            match name.value {
+                Some(_) => {
+                    names.push(name.value);
+                    sc.recover_to(&[';']);
+                }
+                None => errors.push(sc.recover_to(&[';'])),
+            }
+            sc.bump();
+        }
+        assert_eq!(names.len(), 2);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].value, "let 9b = 2");
+        assert_eq!(errors[0].get_byte_pos(), 11..21);
+        assert_eq!(errors[0].get_line_pos(), 2..=2);
+    }
+    #[test]
+    fn test_match_succeeded() {
+        let sc = Scanny::new("abc");
+        assert!(!sc.match_succeeded());
+        assert!(!sc.match_failed());
+        sc.matcher().then('a');
+        assert!(sc.match_succeeded());
+        assert!(!sc.match_failed());
+        sc.then('x');
+        assert!(!sc.match_succeeded());
+        assert!(sc.match_failed());
+        let failed = sc.finalize(|v| v.is_not_matched()).unwrap().value;
+        assert!(failed);
+        assert!(!sc.match_failed());
+        sc.matcher().matched().then('x');
+        assert!(sc.match_succeeded());
+    }
+    #[test]
+    fn test_then_while() {
+        let sc = Scanny::new("123abc");
+        let token = sc
+            .matcher()
+            .then_while(char::is_ascii_digit)
+            .finalize(|v| (v.is_matched(), v.value()))
+            .unwrap();
+        assert_eq!(token.value, (true, "123"));
+        let token = sc
+            .matcher()
+            .then_while(char::is_ascii_digit)
+            .finalize(|v| {
+                v.consume_on_not_match(false);
+                (v.is_matched(), v.value())
+            })
+            .unwrap();
+        assert_eq!(token.value, (false, ""));
+        assert_eq!(sc.peek(), Some('a'));
+        sc.skeep_while(|_| true);
+        let token = sc
+            .matcher()
+            .then_while(char::is_ascii_digit)
+            .finalize(|v| v.is_matched())
+            .unwrap();
+        assert!(!token.value);
+    }
+    #[test]
+    fn test_no_consume() {
+        let sc = Scanny::new("ab");
+        sc.no_consume_on_match();
+        sc.matcher()
+            .no_consume_on_match()
+            .then('a')
+            .finalize(|_| ());
+        assert_eq!(sc.peek(), Some('a'));
+        sc.matcher()
+            .no_consume_on_match()
+            .then('a')
+            .finalize(|v| v.consume_on_match(true));
+        assert_eq!(sc.peek(), Some('b'));
+        sc.matcher()
+            .no_consume_on_match()
+            .then('x')
+            .finalize(|_| ());
+        assert_eq!(sc.peek(), Some('b'));
+        sc.matcher().then('b').finalize(|_| ());
+        assert_eq!(sc.peek(), None);
+    }
+    #[test]
+    fn test_hspace() {
+        let sc = Scanny::new("  \t\nx");
+        sc.skip_hspace();
+        assert_eq!(sc.peek(), Some('\n'));
+        sc.skip_hspace();
+        assert_eq!(sc.peek(), Some('\n'));
+
+        let sc = Scanny::new("a \t\nb\n");
+        let matched = |sc: &Scanny| {
+            sc.matcher()
+                .match_char(char::is_ascii_alphabetic)
+                .match_hspace()
+                .finalize(|v| v.is_matched())
+                .unwrap()
+                .value
+        };
+        assert!(matched(&sc));
+        assert_eq!(sc.bump(), Some('\n'));
+        assert!(!matched(&sc));
+    }
+    #[test]
+    fn test_match_unicode_escape() {
+        let escape = |src| {
+            let sc = Scanny::new(src);
+            let ch = sc.match_unicode_escape().map(|v| v.value);
+            (ch, sc.bytes_consumed())
+        };
+        assert_eq!(escape("{41}"), (Some('A'), 4));
+        assert_eq!(escape("{10FFFF}x"), (Some('\u{10FFFF}'), 8));
+        assert_eq!(escape("{00e9}"), (Some('é'), 6));
+        assert_eq!(escape("{1000000}"), (None, 0));
+        assert_eq!(escape("{110000}"), (None, 0));
+        assert_eq!(escape("{D800}"), (None, 0));
+        assert_eq!(escape("{DFFF}"), (None, 0));
+        assert_eq!(escape("41}"), (None, 0));
+        assert_eq!(escape("{41"), (None, 0));
+        assert_eq!(escape("{}"), (None, 0));
+        assert_eq!(escape("{4g}"), (None, 0));
+        assert_eq!(escape(""), (None, 0));
+
+        let sc = Scanny::new("a\\u{62}");
+        sc.matcher().then('a').then('\\').then('u');
+        let ch = sc.match_unicode_escape().unwrap();
+        assert_eq!(ch.get_byte_pos(), 3..7);
+        let token = sc.finalize(|v| v.value()).unwrap();
+        assert_eq!(token.value, "a\\u{62}");
+    }
+    #[test]
+    fn test_remaining() {
+        let sc = Scanny::new("aé€");
+        assert_eq!(sc.remaining_bytes(), 6);
+        assert_eq!(sc.remaining_chars(), 3);
+        sc.bump();
+        sc.matcher().bump();
+        assert_eq!(sc.remaining_bytes(), 3);
+        assert_eq!(sc.remaining_chars(), 1);
+        sc.finalize(|m| m.consume_on_match(false));
+        assert_eq!(sc.remaining_bytes(), 5);
+        assert_eq!(sc.remaining_chars(), 2);
+        sc.skeep_while(|_| true);
+        assert_eq!(sc.remaining_bytes(), 0);
+        assert_eq!(sc.remaining_chars(), 0);
+    }
+    #[test]
+    fn test_then_any_str_optional() {
+        let suffixes = ["u8", "u16", "i", "i32"];
+        let number = |sc: &Scanny| {
+            sc.skeep_while(char::is_whitespace)
+                .matcher()
+                .consume_while(char::is_ascii_digit)
+                .then_any_str_optional(&suffixes)
+                .finalize(|v| v.is_matched())
+                .unwrap()
+                .span()
+        };
+        let sc = Scanny::new("1i32 2 3u8 4i 5u");
+        let spans: Vec<_> = (0..5).map(|_| number(&sc).get_byte_pos()).collect();
+        assert_eq!(spans, vec![0..4, 5..6, 7..10, 11..13, 14..15]);
+        assert_eq!(sc.peek(), Some('u'));
+    }
+    #[test]
+    fn test_consume_while_ascii_fast_path() {
+        let src = "ab1\ncd2\r\nef3 gh";
+        let scan = |src: &str| {
+            let sc = Scanny::new(src);
+            let mut out = Vec::new();
+            while sc.peek().is_some() {
+                let token = sc
+                    .skeep_while(|v| !v.is_ascii_alphanumeric())
+                    .matcher()
+                    .consume_while(char::is_ascii_alphanumeric)
+                    .finalize(|v| v.value().to_string())
+                    .unwrap();
+                let pos = sc.position();
+                out.push((
+                    token.value.clone(),
+                    token.get_byte_pos(),
+                    token.get_char_pos(),
+                    pos,
+                ));
+            }
+            out
+        };
+        // a non-ASCII separator forces the char by char path
+        let ascii = scan(src);
+        let unicode = scan(&src.replace(' ', "\u{a0}"));
+        assert_eq!(ascii.len(), 4);
+        for (a, b) in ascii.iter().zip(&unicode).take(3) {
+            assert_eq!(a, b);
+        }
+        assert_eq!(ascii[3].3.line, 3);
+        assert_eq!((ascii[3].1.clone(), unicode[3].1.clone()), (13..15, 14..16));
+
+        let sc = Scanny::new("abcdef");
+        sc.set_max_token_bytes(4);
+        let token = sc
+            .matcher()
+            .then('a')
+            .consume_while(|_| true)
+            .finalize(|v| v.is_matched());
+        assert_eq!(token.unwrap().get_byte_pos(), 0..4);
+        assert_eq!(sc.peek(), Some('e'));
+    }
+    #[test]
+    fn test_match_char_literal() {
+        let sc = Scanny::new(r"'a' '\n' '\\' '\q' 'ab' '");
+        let lit = |sc: &Scanny| sc.match_char_literal('\'', '\\').map(|v| v.value);
+        assert_eq!(lit(&sc), Some('a'));
+        sc.bump();
+        assert_eq!(lit(&sc), Some('\n'));
+        sc.bump();
+        let ch = sc.match_char_literal('\'', '\\').unwrap();
+        assert_eq!((ch.value, ch.get_byte_pos()), ('\\', 9..13));
+        for _ in 0..2 {
+            sc.bump();
+            assert_eq!(lit(&sc), None);
+            assert_eq!(sc.peek(), Some('\''));
+            sc.skeep_while(|v| v != ' ');
+        }
+        sc.bump();
+        assert_eq!(lit(&sc), None);
+        assert_eq!(sc.position().byte, 24);
+
+        let sc = Scanny::new("x`y`");
+        sc.matcher().then('x');
+        assert_eq!(sc.match_char_literal('`', '\\').unwrap().value, 'y');
+        assert_eq!(sc.finalize(|v| v.value()).unwrap().value, "x`y`");
+    }
+    #[test]
+    fn test_context() {
+        let sc = Scanny::with_context("12a 3", Vec::new());
+        let number = |sc: &Scanny<Vec<usize>>| {
+            sc.skeep_while(char::is_whitespace)
+                .matcher()
+                .consume_while(char::is_ascii_digit)
+                .then_peek(|sc| match sc.peek() {
+                    Some(ch) if ch.is_ascii_alphabetic() => {
+                        let pos = sc.position().byte;
+                        sc.context_mut().push(pos);
+                        false
+                    }
+                    _ => true,
+                })
+                .finalize(|v| v.is_matched())
+                .unwrap()
+                .value
+        };
+        assert!(!number(&sc));
+        sc.bump();
+        assert!(number(&sc));
+        assert_eq!(*sc.context(), vec![2]);
+        assert_eq!(*sc.clone().context(), vec![2]);
+    }
+    #[test]
+    fn test_then_if_str() {
+        let sc = Scanny::new("a->b");
+        let arrow = |sc: &Scanny, if_str: bool| {
+            let token = sc.matcher().then('a');
+            if if_str {
+                token.then_if_str("->");
+            } else {
+                token.then_unless_str("->");
+            }
+            let matched = token.then('-').finalize(|v| v.is_matched()).unwrap().value;
+            (matched, sc.position().byte)
+        };
+        assert_eq!(arrow(&sc, true), (true, 2));
+        let sc = Scanny::new("a->b");
+        assert_eq!(arrow(&sc, false), (false, 1));
+        let sc = Scanny::new("a-b");
+        assert_eq!(arrow(&sc, true), (false, 1));
+        let sc = Scanny::new("a-b");
+        assert_eq!(arrow(&sc, false), (true, 2));
+
+        let sc = Scanny::new("ab");
+        sc.matcher().then('a').then_if_str("b");
+        assert_eq!(sc.current_match_span(), Some(0..1));
+        sc.then_unless_str("c");
+        assert_eq!(sc.finalize(|v| v.value()).unwrap().value, "a");
+        assert_eq!(sc.peek(), Some('b'));
+    }
+    #[test]
+    fn test_try_tokens() {
+        fn word<'a>(sc: &Scanny<'a>) -> Option<Result<WithPos<&'a str>, char>> {
+            sc.skeep_while(char::is_whitespace);
+            let token = sc
+                .matcher()
+                .consume_while(char::is_ascii_alphabetic)
+                .finalize(|v| v.value())?;
+            if token.value.is_empty() {
+                return Some(Err(sc.peek()?));
+            }
+            Some(Ok(token))
+        }
+        let sc = Scanny::new("ab cd");
+        let words: Result<Vec<_>, _> = sc.try_tokens(word).collect();
+        let words: Vec<_> = words.unwrap().into_iter().map(|v| v.value).collect();
+        assert_eq!(words, vec!["ab", "cd"]);
+
+        let sc = Scanny::new("ab! cd");
+        let results: Vec<_> = sc.try_tokens(word).map(|v| v.map(|v| v.value)).collect();
+        assert_eq!(results, vec![Ok("ab"), Err('!')]);
+        assert_eq!(sc.peek(), Some('!'));
+
+        let sc = Scanny::new("ab");
+        let mut calls = 0;
+        let tokens = sc.try_tokens(|sc| {
+            calls += 1;
+            Some(Ok::<_, ()>(sc.matcher().finalize(|v| v.value())?))
+        });
+        assert_eq!(tokens.count(), 0);
+        assert_eq!(calls, 1);
+    }
+    #[test]
+    fn test_mark_since() {
+        let sc = Scanny::new("héllo world");
+        let mark = sc.mark();
+        sc.matcher().consume_while(|v| v.is_alphabetic());
+        assert_eq!(sc.since(mark), "héllo");
+        sc.finalize(|m| m.consume_on_match(false));
+        assert_eq!(sc.since(mark), "");
+        sc.bump_bytes(7);
+        assert_eq!(sc.since(mark), "héllo ");
+        assert_eq!(sc.since(sc.mark()), "");
+    }
+    #[test]
+    #[should_panic(expected = "byte 2 is not a char boundary")]
+    fn test_since_not_boundary() {
+        let sc = Scanny::new("héllo");
+        sc.bump_bytes(3);
+        sc.since(2);
+    }
+    #[test]
+    #[should_panic(expected = "mark 3 is past the current position 1")]
+    fn test_since_past() {
+        let sc = Scanny::new("hello");
+        sc.bump();
+        sc.since(3);
+    }
+    #[test]
+    fn test_match_quoted_doubled() {
+        let sc = Scanny::new("'it''s a test' '''' 'open''");
+        let quoted = |sc: &Scanny| {
+            sc.skeep_while(char::is_whitespace)
+                .matcher()
+                .match_quoted_doubled('\'')
+                .finalize(|v| (v.is_matched(), v.value().to_string()))
+                .unwrap()
+        };
+        let token = quoted(&sc);
+        assert_eq!(token.value, (true, "'it''s a test'".to_string()));
+        assert_eq!(token.get_byte_pos(), 0..14);
+        assert_eq!(quoted(&sc).value, (true, "''''".to_string()));
+        assert_eq!(quoted(&sc).value, (false, "'open''".to_string()));
+        assert_eq!(sc.peek(), None);
+    }
+    #[test]
+    fn test_locate() {
+        let sc = Scanny::new("a\r\nbc\n\nd");
+        sc.set_line_base(0);
+        while sc.peek().is_some() {
+            assert_eq!(sc.locate(sc.position().byte), Some(sc.position()));
+            sc.bump();
+        }
+        assert_eq!(sc.locate(sc.position().byte), Some(sc.position()));
+        assert_eq!(sc.locate(sc.position().byte + 1), None);
+        assert_eq!(sc.locate(2).unwrap().column, 2);
+    }
+    #[test]
+    fn test_line_starts() {
+        assert_eq!(&*Scanny::new("").line_starts(), &[0]);
+        assert_eq!(&*Scanny::new("\n").line_starts(), &[0, 1]);
+        let sc = Scanny::new("é\r\nb\n");
+        assert_eq!(&*sc.line_starts(), &[0, 4, 6]);
+        assert_eq!(sc.clone().line_starts().len(), 3);
+        assert_eq!(sc.locate(6).unwrap().line, 3);
+        assert_eq!(sc.locate(5).unwrap().line, 2);
+    }
+    #[test]
+    fn test_match_exact_run() {
+        let color = |src: &str| {
+            Scanny::new(src)
+                .matcher()
+                .then('#')
+                .match_exact_run(char::is_ascii_hexdigit, 6)
+                .finalize(|v| (v.is_matched(), v.value().len()))
+                .unwrap()
+                .value
+        };
+        assert_eq!(color("#a0B1c2"), (true, 7));
+        assert_eq!(color("#a0B1c2d"), (true, 7));
+        assert_eq!(color("#a0B1c"), (false, 6));
+        assert_eq!(color("#a0gB1c2"), (false, 3));
+    }
+    #[test]
+    fn test_opt_char() {
+        let sc = Scanny::new("ab::c");
+        assert!(!sc.opt_char('x'));
+        assert!(sc.opt_char('a'));
+        sc.matcher().then('b');
+        assert!(!sc.opt_str(":::"));
+        assert!(sc.opt_str("::"));
+        assert!(sc.match_succeeded());
+        sc.then('x');
+        assert!(!sc.opt_char('c'));
+        let token = sc.finalize(|v| (v.is_matched(), v.value())).unwrap();
+        assert_eq!(token.value, (false, "b::"));
+        assert_eq!(sc.peek(), Some('c'));
+    }
+    #[test]
+    fn test_custom() {
+        let sc = Scanny::new("ab\ncd;");
+        let token = sc
+            .matcher()
+            .custom(|rest| rest.find(';'))
+            .then(';')
+            .finalize(|v| v.is_matched())
+            .unwrap();
+        assert!(token.value);
+        assert_eq!(token.get_line_pos(), 1..=2);
+        assert_eq!(sc.position().column, 4);
+
+        let sc = Scanny::new("ab");
+        let token = sc.matcher().custom(|_| None).finalize(|v| v.value());
+        assert_eq!(token.unwrap().value, "");
+    }
+    #[test]
+    #[should_panic(expected = "custom length 1 is not a char boundary")]
+    fn test_custom_not_boundary() {
+        Scanny::new("é").matcher().custom(|_| Some(1));
+    }
+    #[test]
+    fn test_would_match() {
+        let sc = Scanny::new("ab 12");
+        assert!(sc.would_match(|sc| sc.then('a').then('b')));
+        assert!(!sc.would_match(|sc| sc.then('a').then('c')));
+        assert_eq!(sc.position().byte, 0);
+        assert_eq!(sc.current_match_span(), None);
+
+        sc.matcher().then('a');
+        assert!(sc.would_match(|sc| sc.then('b')));
+        assert!(!sc.would_match(|sc| sc.then('a')));
+        assert_eq!(sc.current_match_span(), Some(0..1));
+        sc.then('x');
+        assert!(sc.would_match(|sc| sc.then('b').then(' ')));
+        assert!(sc.match_failed());
+        let token = sc.finalize(|v| v.value()).unwrap();
+        assert_eq!(token.value, "a");
+        sc.assert_invariants();
+    }
+    #[test]
+    fn test_then_if_prev() {
+        let sc = Scanny::new("ab");
+        assert!(sc.would_match(|sc| sc.then_if_prev(|v| v.is_none())));
+        sc.matcher().then('a').then_if_prev(|v| v == Some('a'));
+        assert_eq!(sc.peek_back(), Some('a'));
+        sc.then('b').then_if_prev(|v| v == Some('a'));
+        assert!(sc.match_failed());
+        assert_eq!(sc.finalize(|v| v.value()).unwrap().value, "ab");
+        assert_eq!(sc.peek_back(), Some('b'));
+    }
+    #[test]
+    fn test_clear_match_flags() {
+        let sc = Scanny::new("a\nbc");
+        assert!(!sc.clear_match_flags().is_matched());
+        sc.matcher().then('a').then('\n').matched();
+        let pos = sc.position();
+        assert!(sc.is_matched());
+        sc.clear_match_flags();
+        assert!(!sc.is_matched());
+        assert!(sc.match_succeeded());
+        assert_eq!(sc.position(), pos);
+        sc.then('x');
+        assert!(sc.match_failed());
+        sc.clear_match_flags();
+        assert_eq!(sc.position(), pos);
+        let token = sc.then('b').finalize(|v| v.value()).unwrap();
+        assert_eq!(token.value, "a\nb");
+        assert_eq!(token.get_line_pos(), 1..=2);
+    }
+    #[test]
+    fn test_consume_to_eol() {
+        let sc = Scanny::new("# a\rb\n# c\r\n#");
+        fn comment<'a>(sc: &Scanny<'a>) -> &'a str {
+            sc.skeep_while(char::is_whitespace)
+                .matcher()
+                .then('#')
+                .consume_to_eol()
+                .finalize(|v| v.value())
+                .unwrap()
+                .value
+        }
+        assert_eq!(comment(&sc), "# a\rb");
+        assert_eq!(sc.peek(), Some('\n'));
+        assert_eq!(comment(&sc), "# c");
+        assert_eq!(sc.peek(), Some('\r'));
+        assert_eq!(comment(&sc), "#");
+        assert!(sc.at_end());
+
+        let sc = Scanny::new("a\r\n");
+        sc.set_crlf(false);
+        sc.matcher().consume_to_eol();
+        assert_eq!(sc.current_match_span(), Some(0..2));
+    }
+    #[test]
+    fn test_match_any_quoted() {
+        let quotes = ['\'', '"', '`'];
+        let sc = Scanny::new(r#"'a\'b' "x'y" `` ab 'open\'"#);
+        let mut found = Vec::new();
+        while let Some(s) = sc.match_any_quoted(&quotes, '\\') {
+            found.push((s.value, s.text(r#"'a\'b' "x'y" `` ab 'open\'"#).to_string()));
+            sc.bump();
+        }
+        assert_eq!(
+            found,
+            vec![
+                ('\'', r"'a\'b'".to_string()),
+                ('"', r#""x'y""#.to_string()),
+                ('`', "``".to_string()),
+            ]
+        );
+        assert_eq!(sc.peek(), Some('a'));
+        sc.bump_bytes(3);
+        assert_eq!(sc.match_any_quoted(&quotes, '\\'), None);
+        assert_eq!(sc.position().byte, 19);
+
+        let sc = Scanny::new("x'y'");
+        sc.matcher().then('x');
+        assert_eq!(
+            sc.match_any_quoted(&quotes, '\\').unwrap().get_byte_pos(),
+            1..4
+        );
+        assert_eq!(sc.finalize(|v| v.value()).unwrap().value, "x'y'");
+    }
+    #[test]
+    fn test_from_parts() {
+        let doc = "ab\r\ncd";
+        for byte in 0..=doc.len() {
+            let sc = Scanny::from_parts(doc, byte).unwrap();
+            let other = Scanny::new(doc);
+            other.bump_bytes(byte);
+            assert_eq!(sc.position(), other.position());
+            assert_eq!(sc.chars_consumed(), byte);
+            sc.assert_invariants();
+        }
+        assert_eq!(
+            Scanny::from_parts(doc, 7).err(),
+            Some(crate::ScanError::InvalidPosition { byte: 7 })
+        );
     }
-}
+    #[test]
+    fn test_consume_if() {
+        let sc = Scanny::new("ab\ncd");
+        assert_eq!(sc.consume_if(|sc| sc.then('a').then('x')), None);
+        assert_eq!(sc.position().byte, 0);
+        let token = sc
+            .consume_if(|sc| sc.then('a').then('b').then('\n'))
+            .unwrap();
+        assert_eq!(token.value, "ab\n");
+        assert_eq!(token.get_line_pos(), 1..=2);
+        assert_eq!(sc.position().line, 2);
+        assert_eq!(sc.current_match_span(), None);
 
-#[cfg(test)]
-mod tests {
-    use super::Scanny;
+        sc.matcher().then('c');
+        assert_eq!(sc.consume_if(|sc| sc.then('x')), None);
+        let d = sc.consume_if(|sc| sc.then('d')).unwrap();
+        assert_eq!(d.get_byte_pos(), 4..5);
+        assert_eq!(sc.finalize(|v| v.value()).unwrap().value, "cd");
+        sc.assert_invariants();
+    }
+    #[test]
+    fn test_finalize_or_error() {
+        let sc = Scanny::new("ab a\nc");
+        fn word<'a>(sc: &Scanny<'a>) -> Result<WithPos<usize>, WithPos<&'a str>> {
+            sc.skeep_while(|v| v == ' ')
+                .matcher()
+                .then('a')
+                .then('b')
+                .finalize_or_error(|v| v.len())
+        }
+        let ok = word(&sc).unwrap();
+        assert_eq!((ok.value, ok.get_byte_pos()), (2, 0..2));
+        let err = word(&sc).unwrap_err();
+        assert_eq!((err.value, err.get_byte_pos()), ("a", 3..4));
+        assert_eq!(sc.peek(), Some('\n'));
 
+        let err = sc.finalize_or_error(|_| ()).unwrap_err();
+        assert_eq!(err.get_byte_pos(), 4..4);
+        let err = sc
+            .matcher()
+            .no_consume_on_not_match()
+            .then('x')
+            .finalize_or_error(|_| ())
+            .unwrap_err();
+        assert_eq!(err.get_byte_pos(), 4..4);
+        sc.bump();
+        let ok = sc
+            .matcher()
+            .no_consume_on_match()
+            .then('c')
+            .finalize_or_error(|_| ());
+        assert_eq!(ok.unwrap().get_line_pos(), 2..=2);
+        assert_eq!(sc.peek(), Some('c'));
+    }
     #[test]
-    fn test_bump() {
-        let sc = Scanny::new("abcd");
-        assert_eq!(sc.bump(), Some('a'));
-        assert_eq!(sc.bump(), Some('b'));
-        assert_eq!(sc.bump(), Some('c'));
-        assert_eq!(sc.bump(), Some('d'));
-        assert_eq!(sc.bump(), None);
+    fn test_consume_block_comment() {
+        let sc = Scanny::new("/** doc\n * more\n */\nfn /* open");
+        let comment = sc.consume_block_comment("/*", "*/").unwrap();
+        assert_eq!(comment.get_byte_pos(), 0..19);
+        assert_eq!(comment.get_line_pos(), 1..=3);
+        let name = sc
+            .skeep_while(char::is_whitespace)
+            .matcher()
+            .consume_while(char::is_ascii_alphabetic)
+            .finalize(|v| v.value())
+            .unwrap();
+        assert_eq!(name.get_line_pos(), 4..=4);
+        assert_eq!(sc.position().column, 3);
+        sc.bump();
+        assert_eq!(sc.consume_block_comment("/*", "*/"), None);
+        assert_eq!(sc.consume_block_comment("//", "\n"), None);
+        assert_eq!(sc.peek(), Some('/'));
+        assert_eq!(Scanny::new("/*/").consume_block_comment("/*", "*/"), None);
+    }
+    #[test]
+    fn test_captures() {
+        let sc = Scanny::new("key=1;x=;");
+        let pair = |sc: &Scanny| {
+            sc.matcher()
+                .capture("key", |sc| sc.then_while(|v| v.is_ascii_alphabetic()))
+                .then('=')
+                .capture("value", |sc| sc.then_while(char::is_ascii_digit))
+                .then(';')
+                .finalize_with_captures(|v, caps| {
+                    let spans = (caps.span("key"), caps.span("value"));
+                    (v.is_matched(), caps.len(), spans)
+                })
+                .unwrap()
+                .value
+        };
+        assert_eq!(pair(&sc), (true, 2, (Some(0..3), Some(4..5))));
+        assert_eq!(pair(&sc), (false, 1, (Some(6..7), None)));
+        assert_eq!(sc.finalize_with_captures(|_, _| ()), None);
 
-        let sc = Scanny::new("abcd");
-        sc.matcher();
-        assert_eq!(sc.bump(), Some('a'));
-        assert_eq!(sc.bump(), Some('b'));
-        assert_eq!(sc.bump(), Some('c'));
-        assert_eq!(sc.bump(), Some('d'));
-        assert_eq!(sc.bump(), None);
-        sc.finalize(|_| {});
-        assert_eq!(sc.bump(), None);
+        let sc = Scanny::new("ab");
+        let caps = sc
+            .matcher()
+            .capture("x", |sc| sc.then('a'))
+            .capture("x", |sc| sc.then('b'))
+            .finalize_with_captures(|_, caps| caps.clone())
+            .unwrap()
+            .value;
+        assert_eq!(caps.get("x"), Some("b"));
+        assert_eq!(caps.iter().count(), 2);
+        assert_eq!(caps.get("y"), None);
+    }
+    #[test]
+    fn test_peek_while_count() {
+        let sc = Scanny::new("123456x");
+        assert_eq!(sc.peek_while_count(|v| v == 'x'), 0);
+        sc.matcher().then('1');
+        assert_eq!(sc.peek_while_count(|v| v.is_ascii_digit()), 5);
+        let token = sc
+            .then_peek(|sc| sc.peek_while_count(|v| v.is_ascii_digit()) <= 4)
+            .consume_while(char::is_ascii_digit)
+            .finalize(|v| v.is_matched())
+            .unwrap();
+        assert!(!token.value);
+        assert_eq!(token.get_byte_pos(), 0..1);
+    }
+    #[test]
+    fn test_last_span() {
+        let sc = Scanny::new("a.b .c");
+        let mut adjacent = Vec::new();
+        while !sc.at_end() {
+            sc.skeep_while(char::is_whitespace);
+            let prev_end = sc.last_span().map(|v| v.get_byte_pos().end);
+            sc.matcher().match_one();
+            sc.finalize(|_| ());
+            adjacent.push(prev_end == Some(sc.last_span().unwrap().get_byte_pos().start));
+        }
+        assert_eq!(adjacent, vec![false, true, true, false, true]);
 
-        let sc = Scanny::new("abcd");
+        sc.matcher().then('x').finalize(|_| ());
+        assert_eq!(sc.last_span().unwrap().get_byte_pos(), 6..6);
+        assert_eq!(sc.last_span().unwrap().get_line_pos(), 1..=1);
+    }
+    #[test]
+    fn test_matcher_and_scanner_cursor() {
+        let sc = Scanny::new("ab\ncd ef");
         sc.matcher();
+        // moving methods act on the matcher only
         assert_eq!(sc.bump(), Some('a'));
-        assert_eq!(sc.bump(), Some('b'));
-        assert_eq!(sc.bump(), Some('c'));
-        assert_eq!(sc.bump(), Some('d'));
-        assert_eq!(sc.bump(), None);
-        sc.finalize(|m| {
-            m.consume_on_match(false);
-        });
-        assert_eq!(sc.bump(), Some('a'));
-        assert_eq!(sc.bump(), Some('b'));
-        assert_eq!(sc.bump(), Some('c'));
-        assert_eq!(sc.bump(), Some('d'));
-        assert_eq!(sc.bump(), None);
+        sc.skeep_while(|v| v != '\n');
+        assert_eq!(sc.bump_bytes(1), "\n");
+        sc.matcher();
+        sc.clone().bump();
+        assert_eq!(sc.position().byte, 4);
+        assert_eq!(sc.bytes_consumed(), 4);
+        assert_eq!(*sc.byte_pos.borrow(), 0);
+        assert_eq!(*sc.line.borrow(), 1);
+        sc.assert_invariants();
+        let token = sc.finalize(|v| v.value()).unwrap();
+        assert_eq!(token.value, "ab\nc");
+        assert_eq!(token.get_line_pos(), 1..=2);
+        assert_eq!(*sc.byte_pos.borrow(), 4);
 
-        let sc = Scanny::new("abcd");
-        sc.bump(); // consume 'a'
-        sc.bump(); // consume 'b'
+        // without a matcher the scanner moves right away
+        sc.bump();
+        assert_eq!(*sc.byte_pos.borrow(), 5);
+
+        // a match marked as matched finalizes as `All`, even if a later
+        // combinator failed
+        sc.matcher()
+            .then(' ')
+            .matched()
+            .clear_match_flags()
+            .then('x');
+        assert!(sc.match_failed());
+        sc.matched();
+        assert!(sc.finalize(|v| v.is_matched()).unwrap().value);
+        sc.assert_invariants();
+    }
+    #[test]
+    fn test_skip_line_continuation() {
+        let sc = Scanny::new("a \\\r\n  b \\ c\\");
+        let words = || {
+            sc.skip_hspace()
+                .skip_line_continuation()
+                .skip_hspace()
+                .matcher()
+                .consume_while(|v| !v.is_whitespace())
+                .finalize(|v| v.value())
+                .unwrap()
+        };
+        assert_eq!(words().get_line_pos(), 1..=1);
+        let b = words();
+        assert_eq!((b.value, b.get_line_pos()), ("b", 2..=2));
+        assert_eq!(words().value, "\\");
+        sc.skip_line_continuation();
+        assert_eq!(sc.peek(), Some(' '));
+        assert_eq!(words().value, "c\\");
+        assert!(sc.skip_line_continuation().at_end());
+    }
+    #[test]
+    fn test_bump_spanned() {
+        let sc = Scanny::new("(\n)");
+        let open = sc.bump_spanned().unwrap();
+        assert_eq!(open.get_char_pos(), 0..1);
+        let nl = sc.bump_spanned().unwrap();
+        assert_eq!((nl.get_byte_pos(), nl.get_line_pos()), (1..2, 1..=1));
         sc.matcher();
-        assert_eq!(sc.bump(), Some('c'));
-        assert_eq!(sc.bump(), Some('d'));
-        assert_eq!(sc.bump(), None);
-        sc.finalize(|m| {
-            m.consume_on_match(false);
-        });
-        assert_eq!(sc.bump(), Some('c'));
-        assert_eq!(sc.bump(), Some('d'));
-        assert_eq!(sc.bump(), None);
+        let close = sc.bump_spanned().unwrap();
+        assert_eq!((close.get_byte_pos(), close.get_line_pos()), (2..3, 2..=2));
+        assert_eq!(sc.bump_spanned(), None);
+        assert_eq!(sc.finalize(|v| v.value()).unwrap().value, ")");
     }
     #[test]
-    fn test_peek() {
-        let sc = Scanny::new("abcd");
-        assert_eq!(sc.peek(), Some('a'));
-        assert_eq!(sc.peek(), Some('a'));
-        assert_eq!(sc.bump(), Some('a'));
-        assert_eq!(sc.peek(), Some('b'));
-        assert_eq!(sc.peek(), Some('b'));
-        assert_eq!(sc.bump(), Some('b'));
-        assert_eq!(sc.peek(), Some('c'));
-        assert_eq!(sc.peek(), Some('c'));
-        assert_eq!(sc.bump(), Some('c'));
-        assert_eq!(sc.peek(), Some('d'));
-        assert_eq!(sc.peek(), Some('d'));
-        assert_eq!(sc.bump(), Some('d'));
-        assert_eq!(sc.peek(), None);
-        assert_eq!(sc.bump(), None);
+    fn test_dotted() {
+        fn ident<'a>(sc: &Scanny<'a>) -> Option<WithPos<&'a str>> {
+            let token = sc
+                .matcher()
+                .then_while(char::is_ascii_alphabetic)
+                .finalize(|v| v.is_matched().then(|| v.value()))?;
+            let value = token.value?;
+            Some(token.with_value(value))
+        }
+        let sc = Scanny::new("foo.bar.baz");
+        let path = sc.dotted('.', ident);
+        let spans: Vec<_> = path.value.iter().map(|v| v.get_byte_pos()).collect();
+        assert_eq!(spans, vec![0..3, 4..7, 8..11]);
+        assert_eq!(path.get_byte_pos(), 0..11);
+        assert!(sc.at_end());
 
-        let sc = Scanny::new("abcd");
-        sc.bump();
-        sc.matcher();
-        assert_eq!(sc.peek(), Some('b'));
-        assert_eq!(sc.bump(), Some('b'));
-        sc.finalize(|_| {});
-        assert_eq!(sc.peek(), Some('c'));
-        assert_eq!(sc.bump(), Some('c'));
-        sc.matcher();
-        assert_eq!(sc.peek(), Some('d'));
-        assert_eq!(sc.bump(), Some('d'));
-        sc.finalize(|_| {});
-        assert_eq!(sc.peek(), None);
-        assert_eq!(sc.bump(), None);
-        sc.matcher();
-        assert_eq!(sc.peek(), None);
-        assert_eq!(sc.bump(), None);
-        sc.finalize(|_| {});
-        assert_eq!(sc.peek(), None);
-        assert_eq!(sc.bump(), None);
+        let sc = Scanny::new("a::b");
+        assert_eq!(sc.dotted(':', ident).value.len(), 1);
+        assert_eq!(sc.position().byte, 1);
 
-        let sc = Scanny::new("abcd");
-        assert_eq!(sc.peek(), Some('a'));
-        assert_eq!(sc.peek_second(), Some('b'));
-        assert_eq!(sc.peek_third(), Some('c'));
-        assert_eq!(sc.peek_nth(3), Some('d'));
-        assert_eq!(sc.peek_nth(4), None);
-        assert_eq!(sc.bump(), Some('a'));
+        let sc = Scanny::new("1.a");
+        let path = sc.dotted('.', ident);
+        assert!(path.value.is_empty());
+        assert_eq!(path.get_byte_pos(), 0..0);
+        assert_eq!(sc.peek(), Some('1'));
+    }
+    #[test]
+    fn test_predicate_by_reference() {
+        let digits = String::from("0123456789");
+        let not_digit = {
+            let digits = digits.clone();
+            move |v: char| !digits.contains(v)
+        };
+        let digit = move |v: &char| digits.contains(*v);
+        let sc = Scanny::new("12a345b6");
+        let mut numbers = Vec::new();
+        while !sc.at_end() {
+            sc.skeep_while(&not_digit);
+            let token = sc
+                .matcher()
+                .match_char(&digit)
+                .consume_while(&digit)
+                .finalize(|v| v.value());
+            numbers.extend(token.map(|v| v.value));
+        }
+        assert_eq!(numbers, vec!["12", "345", "6"]);
 
-        let sc = Scanny::new("abcd");
-        sc.bump(); // a
-        sc.matcher();
-        sc.bump(); // b
-        sc.bump(); // c
-        sc.finalize(|m| m.consume_on_match(false));
-        assert_eq!(sc.bump(), Some('b'));
-        assert_eq!(sc.bump(), Some('c'));
-        assert_eq!(sc.bump(), Some('d'));
+        let sc = Scanny::new("123");
+        assert_eq!(sc.peek_while_count(&not_digit), 0);
+        sc.matcher().match_exact_run(&digit, 2);
+        assert_eq!(sc.finalize(|v| v.value()).unwrap().value, "12");
     }
     #[test]
-    fn test_skeep_while() {
-        let sc = Scanny::new("    1234abc   ");
-        sc.skeep_while(char::is_whitespace);
-        assert_eq!(sc.bump(), Some('1'));
-        sc.skeep_while(|v| v.is_ascii_digit());
-        assert_eq!(sc.bump(), Some('a'));
+    fn test_then_digit_alpha_alnum() {
+        let sc = Scanny::new("x9Z0é");
+        sc.matcher()
+            .then_alpha()
+            .then_digit()
+            .then_alnum()
+            .then_alnum();
+        assert!(sc.match_succeeded());
+        assert_eq!(sc.finalize(|v| v.value()).unwrap().value, "x9Z0");
 
-        let sc = Scanny::new("    1234abc   ");
-        sc.matcher();
-        sc.skeep_while(char::is_whitespace);
-        assert_eq!(sc.bump(), Some('1'));
-        sc.skeep_while(|v| v.is_ascii_digit());
-        assert_eq!(sc.bump(), Some('a'));
-        sc.finalize(|m| {
-            m.consume_on_match(false);
-            m.consume_on_not_match(false);
-        });
-        sc.skeep_while(char::is_whitespace);
-        assert_eq!(sc.bump(), Some('1'));
+        for chain in [Scanny::then_digit, Scanny::then_alpha, Scanny::then_alnum] {
+            let sc = Scanny::new("é");
+            assert!(chain(sc.matcher()).match_failed());
+            assert_eq!(sc.peek(), Some('é'));
+        }
+        let sc = Scanny::new("a");
+        assert!(sc.matcher().then_digit().match_failed());
+        let sc = Scanny::new("1");
+        assert!(sc.matcher().then_alpha().match_failed());
     }
     #[test]
-    fn test_match_char() {
-        let sc = Scanny::new("1234abc");
-        sc.match_char(|v| *v == '1');
-        assert_eq!(sc.bump(), Some('2'));
+    fn test_subscanner() {
+        let src = "let s = {\n\ta é\n  b\n}; c";
+        let sc = Scanny::new(src);
+        sc.set_tab_width(4);
+        sc.set_line_base(0);
+        let open = src.find('{').unwrap() + 1;
+        let close = src.find('}').unwrap();
+        let body = sc.subscanner(open..close);
+        let mut words = Vec::new();
+        while !body.skeep_while(char::is_whitespace).at_end() {
+            let token = body
+                .matcher()
+                .consume_while(|v| !v.is_whitespace())
+                .finalize(|v| v.value())
+                .unwrap();
+            assert_eq!(token.text(src), token.value);
+            let start = body.locate(token.get_byte_pos().start).unwrap();
+            words.push((token.value, token.get_line_pos(), start.column));
+        }
+        assert_eq!(
+            words,
+            vec![("a", 1..=1, 5), ("é", 1..=1, 7), ("b", 2..=2, 3)]
+        );
+        assert_eq!(body.position().byte, close);
+        assert_eq!(sc.position().byte, 0);
+        assert_eq!(sc.subscanner(4..4).peek(), None);
+    }
 
-        let sc = Scanny::new("1234abc");
-        sc.match_char(|v| *v == '2');
-        assert_eq!(sc.bump(), Some('1'));
+    #[test]
+    #[should_panic(expected = "not on char boundaries")]
+    fn test_subscanner_char_boundary() {
+        Scanny::new("aé").subscanner(0..2);
+    }
+    #[test]
+    fn test_bump_until() {
+        let sc = Scanny::new("a\nbé;c");
+        let token = sc.bump_until(';');
+        assert_eq!(token.value, "a\nbé");
+        assert_eq!(token.get_byte_pos(), 0..5);
+        assert_eq!(token.get_line_pos(), 1..=2);
+        assert_eq!(token.get_char_pos(), 0..4);
+        assert_eq!(sc.position().line, 2);
+        assert_eq!(sc.bump_until(';').value, "");
+        assert_eq!(sc.peek(), Some(';'));
 
-        let sc = Scanny::new("1234abc");
-        sc.matcher()
-            .match_char(|v| *v == '1')
-            .match_char(|v| *v == '2');
-        sc.bump();
-        sc.bump();
-        sc.match_char(|v| *v == 'a').match_char(|v| *v == 'b');
-        let matched = sc.finalize(|m| {
-            assert!(m.is_matched());
-            assert!(!m.is_not_matched());
-            m.value()
-        });
-        assert_eq!(matched.unwrap().value, "1234ab");
-        assert_eq!(sc.bump(), Some('c'));
-        assert_eq!(sc.bump(), None);
+        sc.matcher().then(';');
+        let rest = sc.bump_until('x');
+        assert_eq!((rest.value, rest.get_byte_pos()), ("c", 6..7));
+        assert_eq!(sc.finalize(|v| v.value()).unwrap().value, ";c");
+        assert!(sc.at_end());
+        assert_eq!(sc.bump_until('x').get_byte_pos(), 7..7);
+    }
+    #[test]
+    fn test_then_sequence() {
+        let escape = |sc: &Scanny| {
+            sc.matcher()
+                .then_sequence(&['\\', 'n'])
+                .finalize(|v| v.is_matched())
+                .unwrap()
+        };
+        let sc = Scanny::new("\\n\\");
+        let token = escape(&sc);
+        assert!(token.value);
+        assert_eq!(token.get_byte_pos(), 0..2);
+        let token = escape(&sc);
+        assert!(!token.value);
+        assert_eq!(token.get_byte_pos(), 2..2);
+        assert_eq!(sc.peek(), Some('\\'));
 
-        let sc = Scanny::new("1234abc");
-        sc.matcher()
-            .match_char(|v| *v == '1')
-            .match_char(|v| *v == '2');
-        sc.bump();
-        sc.bump();
-        sc.match_char(|v| *v == 'b').match_char(|v| *v == 'b');
-        let matched = sc.finalize(|m| {
-            assert!(!m.is_matched());
-            assert!(m.is_not_matched());
-            m.value()
-        });
-        assert_eq!(matched.unwrap().value, "1234");
-        assert_eq!(sc.bump(), Some('a'));
+        let sc = Scanny::new("é\nx");
+        sc.matcher().then_sequence(&['é', '\n']).then('x');
+        assert!(sc.match_succeeded());
+        let token = sc.finalize(|_| ()).unwrap();
+        assert_eq!(token.get_line_pos(), 1..=2);
+        assert_eq!(sc.position().line, 2);
+        assert!(sc.matcher().then_sequence(&[]).match_succeeded());
     }
     #[test]
-    fn test_peek_and_consume() {
+    fn test_peek_and_consume_spanned() {
         let sc = Scanny::new(r"    'ab\' cd''hello world'   ");
         sc.skeep_while(char::is_whitespace);
-        let matched = sc
+        sc.matcher().then('\'');
+        let body = sc.peek_and_consume_spanned(|v| match v.peek() {
+            Some('\\') => {
+                v.bump();
+                true
+            }
+            Some('\'') => false,
+            _ => true,
+        });
+        assert_eq!(body.value, r"ab\' cd");
+        assert_eq!(body.get_byte_pos(), 5..12);
+        assert_eq!(body.get_char_pos(), 5..12);
+        sc.then('\'');
+        let token = sc.finalize(|v| v.value()).unwrap();
+        assert_eq!((token.value, token.get_byte_pos()), (r"'ab\' cd'", 4..13));
+        assert_eq!(sc.bump(), Some('\''));
+
+        let sc = Scanny::new("a\nb");
+        let all = sc.peek_and_consume_spanned(|_| true);
+        assert_eq!(all.value, "a\nb");
+        assert_eq!(all.get_line_pos(), 1..=2);
+        assert!(sc.at_end());
+        assert_eq!(sc.peek_and_consume_spanned(|_| true).get_byte_pos(), 3..3);
+
+        let sc = Scanny::new("ab");
+        assert!(sc.peek_and_consume(|_| true).at_end());
+    }
+    #[test]
+    fn test_consume_while_in_matcher() {
+        let hex = ['0'..='9', 'a'..='f', 'A'..='F'];
+        let sc = Scanny::new("x1fg");
+        let token = sc
             .matcher()
-            .then('\'')
-            .peek_and_consume(|v| match v.peek() {
-                Some('\\') => {
-                    v.bump();
-                    true
-                }
-                Some('\'') => false,
-                _ => true,
-            })
-            .then('\'')
-            .finalize(|v| v.value())
+            .then('x')
+            .consume_while_in(&hex)
+            .then('g')
+            .finalize(|v| (v.is_matched(), v.value()))
+            .unwrap();
+        assert_eq!(token.value, (true, "x1fg"));
+        assert_eq!(token.get_byte_pos(), 0..4);
+
+        let sc = Scanny::new("x1fg");
+        sc.matcher().then('y').consume_while_in(&hex);
+        assert!(sc.match_failed());
+        sc.finalize(|v| v.consume_on_not_match(false));
+        assert_eq!(sc.peek(), Some('x'));
+    }
+    #[test]
+    fn test_try_tokens_stops_after_none() {
+        let calls = std::cell::Cell::new(0);
+        let sc = Scanny::new("ab");
+        let mut tokens = sc.try_tokens(|sc| {
+            calls.set(calls.get() + 1);
+            let token = sc.bump_spanned()?;
+            (token.value == 'a').then_some(Ok::<_, ()>(token))
+        });
+        assert!(tokens.next().is_some());
+        assert!(tokens.next().is_none());
+        assert!(tokens.next().is_none());
+        assert_eq!(calls.get(), 2);
+    }
+    #[test]
+    fn test_longest_last_span() {
+        fn word(sc: &Scanny, word: &str) -> Option<WithPos<usize>> {
+            sc.matcher();
+            word.chars().for_each(|c| {
+                sc.then(c);
+            });
+            let token = sc.finalize(|v| {
+                v.consume_on_not_match(false);
+                v.is_matched().then(|| v.value().len())
+            })?;
+            let value = token.value?;
+            Some(token.with_value(value))
+        }
+        type Alt = dyn Fn(&Scanny) -> Option<WithPos<usize>>;
+        let sc = Scanny::new("abc");
+        let alts: [&Alt; 3] = [&|sc| word(sc, "ab"), &|sc| word(sc, "abc"), &|sc| {
+            word(sc, "a")
+        }];
+        let token = sc.longest(&alts).unwrap();
+        assert_eq!(token.value, 3);
+        assert_eq!(sc.last_span(), Some(token.span()));
+        assert_eq!(sc.last_span().unwrap().get_byte_pos(), 0..3);
+
+        let sc = Scanny::new("ab x");
+        sc.matcher().then('a').then('b').finalize(|_| ());
+        let before = sc.last_span();
+        let alts: [&Alt; 1] = [&|sc| word(sc, "y")];
+        assert_eq!(sc.longest(&alts), None);
+        assert_eq!(sc.last_span(), before);
+    }
+    #[test]
+    fn test_extend_match_keeps_note() {
+        let sc = Scanny::new("12.5");
+        let int = sc
+            .matcher()
+            .consume_while(char::is_ascii_digit)
+            .finalize(|v| v.value().len())
             .unwrap()
-            .value;
-        assert_eq!(matched, r"'ab\' cd'");
-        assert_eq!(sc.bump(), Some('\''));
-        assert_eq!(sc.bump(), Some('h'));
+            .with_note("octal?");
+        let num = sc.extend_match(int, |sc, n| {
+            sc.matcher()
+                .then('.')
+                .consume_while(char::is_ascii_digit)
+                .finalize(|v| n + v.value().len())
+                .map_or(n, |v| v.value)
+        });
+        assert_eq!(num.value, 4);
+        assert_eq!(num.note(), Some("octal?"));
+        assert_eq!(num.get_byte_pos(), 0..4);
+        assert_eq!(num.get_char_pos(), 0..4);
+    }
+    #[test]
+    fn test_subscanner_shares_context() {
+        let sc = Scanny::with_context("a [b] c", Vec::new());
+        let inner = sc.subscanner(3..4);
+        let ch = inner.bump_spanned().unwrap();
+        inner.context_mut().push(ch.get_byte_pos());
+        assert_eq!(*sc.context(), vec![3..4]);
+    }
+    /// Return the combinator names traced while `f` runs on `src`.
+    fn traced(src: &str, f: impl Fn(&Scanny)) -> Vec<String> {
+        let names = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let sc = Scanny::new(src);
+        let log = std::rc::Rc::clone(&names);
+        sc.set_trace(move |name, _| log.borrow_mut().push(name.to_string()));
+        f(&sc);
+        names.take()
+    }
+    #[test]
+    fn test_trace_once() {
+        let class = CharClass::new().ascii_alpha();
+        let names = traced("ab1", |sc| {
+            sc.consume_class(&class);
+        });
+        assert_eq!(names, vec!["consume_class"]);
+        let names = traced("ab;", |sc| {
+            sc.consume_until_any(&[';']);
+        });
+        assert_eq!(names, vec!["consume_until_any"]);
+        for crlf in [true, false] {
+            let names = traced("ab\r\n", |sc| {
+                sc.set_crlf(crlf);
+                sc.consume_to_eol();
+            });
+            assert_eq!(names, vec!["consume_to_eol"]);
+        }
+        let names = traced("  x", |sc| {
+            sc.then_while(|v| *v == ' ');
+            sc.match_hspace();
+        });
+        assert_eq!(names, vec!["then_while", "then_while"]);
+        let names = traced("42", |sc| {
+            sc.consume_while_if(|_| true, char::is_ascii_digit);
+        });
+        assert_eq!(names, vec!["consume_while_if"]);
+        let names = traced("ab", |sc| {
+            sc.then_range('a'..='z').then_ranges(&['a'..='z']);
+        });
+        assert_eq!(names, vec!["then_range", "then_ranges"]);
+        let names = traced("ab", |sc| {
+            sc.consume_while_in(&['a'..='z']);
+        });
+        assert_eq!(names, vec!["consume_while_in"]);
+    }
+    #[test]
+    #[should_panic(expected = "start 3 is past the current position 1")]
+    fn test_spanned_start_past_current() {
+        let ahead = Scanny::new("a\nb");
+        ahead.bump_bytes(3);
+        let sc = Scanny::new("a\nb");
+        sc.bump();
+        sc.spanned(ahead.position(), ());
+    }
+    #[test]
+    fn test_consume_if_after_failed_match() {
+        let sc = Scanny::new("ab");
+        sc.matcher().then('x');
+        assert_eq!(sc.consume_if(|sc| sc.then('a')), None);
+        assert!(sc.finalize(|v| v.is_not_matched()).unwrap().value);
+        assert_eq!(sc.peek(), Some('a'));
     }
 }
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use crate::scanner::Scanny;
+
+/// Counters of the work done by a [Scanny], to find hot spots in a
+/// grammar. Only available with the `profiling` feature.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScanStats {
+    /// Calls to [`Scanny::bump`], counting each char the ASCII fast path of
+    /// [`Scanny::consume_while`] skips as one call.
+    pub bumps: usize,
+    /// Calls to [`Scanny::peek`] and the other `peek_*` methods.
+    pub peeks: usize,
+    /// Times the cursor was moved back or a match was dropped without
+    /// consuming it.
+    pub backtracks: usize,
+    /// Bytes the scanner or its matchers moved over, including input
+    /// later given back.
+    pub bytes_scanned: usize,
+    /// Calls to each combinator, by name.
+    pub combinators: HashMap<&'static str, usize>,
+}
+
+impl<C> Scanny<'_, C> {
+    /// Return the counters collected since the scanner was created or
+    /// [`Self::reset_stats`] was called.
+    /// # Example
+    /// ```rust
+    /// use scanny::Scanny;
+    ///
+    /// let sc = Scanny::new("abc");
+    /// sc.matcher().then('a').then('x').finalize(|v| v.consume_on_not_match(false));
+    /// let stats = sc.stats();
+    /// assert_eq!(stats.bumps, 1);
+    /// assert_eq!(stats.backtracks, 1);
+    /// assert_eq!(stats.combinators["then"], 2);
+    /// ```
+    pub fn stats(&self) -> ScanStats {
+        self.stats.borrow().clone()
+    }
+    /// Set all counters back to zero.
+    pub fn reset_stats(&self) {
+        *self.stats.borrow_mut() = ScanStats::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Scanny;
+
+    #[test]
+    fn test_stats() {
+        let sc = Scanny::new("ab\ncd");
+        sc.peek();
+        sc.peek_nth(2);
+        sc.bump();
+        sc.bump_bytes(3);
+        let stats = sc.stats();
+        assert_eq!((stats.bumps, stats.peeks, stats.bytes_scanned), (1, 2, 4));
+        assert!(stats.combinators.is_empty());
+
+        sc.reset_stats();
+        assert!(sc.would_match(|sc| sc.then('d')));
+        assert!(!sc.would_match(|sc| sc.then('x')));
+        assert_eq!(sc.stats().backtracks, 0);
+        assert_eq!(sc.parse_u64(10), None);
+        let stats = sc.stats();
+        assert_eq!(stats.backtracks, 1);
+        assert_eq!(stats.combinators["then"], 2);
+        assert_eq!(stats.combinators["matcher"], 2);
+
+        sc.reset_stats();
+        sc.matcher().consume_while(|_| true).finalize(|_| ());
+        assert_eq!(sc.stats().backtracks, 0);
+        assert_eq!(sc.stats().bytes_scanned, 1);
+
+        let ascii = Scanny::new("abc!");
+        let unicode = Scanny::new("äbc!");
+        for sc in [&ascii, &unicode] {
+            sc.consume_while(|v| v.is_alphabetic());
+            assert_eq!(sc.stats().bumps, 3);
+        }
+    }
+}
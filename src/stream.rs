@@ -0,0 +1,167 @@
+use std::task::Poll;
+
+use crate::{pos::WithPos, scanner::Scanny};
+
+/// Scanner over input that arrives in chunks, e.g. from a REPL or a socket.
+///
+/// Input is appended with [`StreamScanny::feed`] and tokens are read with
+/// [`StreamScanny::scan`]. Positions are counted from the start of the
+/// first chunk. Consumed input is dropped from the buffer.
+#[derive(Debug)]
+pub struct StreamScanny {
+    buf: String,
+    byte_pos: usize,
+    char_pos: usize,
+    line: usize,
+    finished: bool,
+}
+
+impl Default for StreamScanny {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamScanny {
+    pub fn new() -> Self {
+        Self {
+            buf: String::new(),
+            byte_pos: 0,
+            char_pos: 0,
+            line: 1,
+            finished: false,
+        }
+    }
+    /// Append `chunk` to the buffered input.
+    pub fn feed(&mut self, chunk: &str) {
+        self.buf.push_str(chunk);
+    }
+    /// Mark the end of the input, a token reaching the end of the buffer is
+    /// then complete.
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+    /// Return `true` if there is no buffered input left.
+    pub fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+    /// Run `f` on the buffered input.
+    ///
+    /// If `f` consumes the whole buffer before [`Self::finish`] is called,
+    /// the token may go on in the next chunk: nothing is consumed and
+    /// `Poll::Pending` is returned, feed more input and scan again.
+    /// Otherwise the input consumed by `f` is dropped and its token is
+    /// returned with absolute positions.
+    /// # Example
+    /// ```rust
+    /// use std::task::Poll;
+    /// use scanny::{Scanny, StreamScanny, WithPos};
+    ///
+    /// fn word(sc: &Scanny) -> Option<WithPos<String>> {
+    ///     sc.skeep_while(char::is_whitespace)
+    ///         .matcher()
+    ///         .consume_while(char::is_ascii_alphabetic)
+    ///         .finalize(|v| v.value().to_string())
+    /// }
+    /// let mut stream = StreamScanny::new();
+    /// stream.feed("hello wo");
+    /// assert_eq!(stream.scan(word).map(|v| v.unwrap().value), Poll::Ready("hello".to_string()));
+    /// assert!(stream.scan(word).is_pending());
+    /// stream.feed("rld\n");
+    /// let token = stream.scan(word);
+    /// let Poll::Ready(Some(token)) = token else { panic!() };
+    /// assert_eq!(token.value, "world");
+    /// assert_eq!(token.get_byte_pos(), 6..11);
+    /// ```
+    pub fn scan<T, F>(&mut self, f: F) -> Poll<Option<WithPos<T>>>
+    where
+        F: for<'b> FnOnce(&Scanny<'b>) -> Option<WithPos<T>>,
+    {
+        let sc = Scanny::new(&self.buf);
+        let token = f(&sc);
+        let consumed = sc.bytes_consumed();
+        let chars = sc.chars_consumed();
+        if !self.finished && consumed == self.buf.len() {
+            return Poll::Pending;
+        }
+        let lines = sc.position().line - 1;
+        let token = token.map(|v| {
+            let byte_pos = v.get_byte_pos();
+            let line_pos = v.get_line_pos();
+            let char_pos = v.get_char_pos();
+            v.set_byte_pos(byte_pos.start + self.byte_pos..byte_pos.end + self.byte_pos)
+                .set_line_pos(line_pos.start() + self.line - 1..=line_pos.end() + self.line - 1)
+                .set_char_pos(char_pos.start + self.char_pos..char_pos.end + self.char_pos)
+        });
+        self.buf.drain(..consumed);
+        self.byte_pos += consumed;
+        self.char_pos += chars;
+        self.line += lines;
+        Poll::Ready(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::task::Poll;
+
+    use super::StreamScanny;
+    use crate::{Scanny, WithPos};
+
+    fn number(sc: &Scanny) -> Option<WithPos<u32>> {
+        sc.skeep_while(char::is_whitespace);
+        sc.peek()?;
+        sc.matcher()
+            .consume_while(char::is_ascii_digit)
+            .finalize(|v| v.value().parse().unwrap())
+    }
+
+    #[test]
+    fn test_stream() {
+        let mut stream = StreamScanny::new();
+        assert!(stream.scan(number).is_pending());
+        stream.feed("12 3");
+        let Poll::Ready(Some(token)) = stream.scan(number) else {
+            panic!()
+        };
+        assert_eq!(token.value, 12);
+        assert!(stream.scan(number).is_pending());
+        stream.feed("4\n\n5");
+        let Poll::Ready(Some(token)) = stream.scan(number) else {
+            panic!()
+        };
+        assert_eq!((token.value, token.get_byte_pos()), (34, 3..5));
+        assert!(stream.scan(number).is_pending());
+        stream.feed("67\n");
+        let Poll::Ready(Some(token)) = stream.scan(number) else {
+            panic!()
+        };
+        assert_eq!(token.value, 567);
+        assert_eq!(token.get_byte_pos(), 7..10);
+        assert_eq!(token.get_line_pos(), 3..=3);
+        assert_eq!(token.get_char_pos(), 7..10);
+        assert!(stream.scan(number).is_pending());
+        stream.finish();
+        assert_eq!(stream.scan(number), Poll::Ready(None));
+        assert!(stream.is_empty());
+    }
+
+    #[test]
+    fn test_default_matches_new() {
+        let scan = |mut stream: StreamScanny| {
+            stream.feed("1\n22\n");
+            stream.finish();
+            let mut tokens = Vec::new();
+            while let Poll::Ready(Some(token)) = stream.scan(number) {
+                tokens.push((token.get_byte_pos(), token.get_line_pos()));
+            }
+            tokens
+        };
+        let tokens = scan(StreamScanny::default());
+        assert_eq!(tokens, scan(StreamScanny::new()));
+        assert_eq!(tokens[1].1, 2..=2);
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use crate::{pos::WithPos, scanner::Scanny};
+
+/// Handle to a string interned in a [SymbolTable].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// Return the index of this symbol in its table.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Deduplicating table of string slices borrowed from the input.
+#[derive(Debug, Default)]
+pub struct SymbolTable<'a> {
+    ids: HashMap<&'a str, Symbol>,
+    strings: Vec<&'a str>,
+}
+
+impl<'a> SymbolTable<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Return the symbol of `value`, adding it to the table if needed.
+    /// # Panics
+    /// Panics if the table already holds `u32::MAX + 1` strings.
+    pub fn intern(&mut self, value: &'a str) -> Symbol {
+        if let Some(sym) = self.ids.get(value) {
+            return *sym;
+        }
+        let sym = Symbol(u32::try_from(self.strings.len()).expect("symbol table is full"));
+        self.strings.push(value);
+        self.ids.insert(value, sym);
+        sym
+    }
+    /// Return the symbol of `value` if it was interned.
+    pub fn get(&self, value: &str) -> Option<Symbol> {
+        self.ids.get(value).copied()
+    }
+    /// Return the string of `sym`.
+    pub fn resolve(&self, sym: Symbol) -> &'a str {
+        self.strings[sym.index()]
+    }
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+impl<'a, C> Scanny<'a, C> {
+    /// Consume the `Matcher` instance and intern the matched part in `table`.
+    ///
+    /// Return `None` if there is no matcher or the token is not matched,
+    /// the partial match is still consumed as with [`Scanny::finalize`].
+    /// # Example
+    /// ```rust
+    /// use scanny::{Scanny, SymbolTable};
+    ///
+    /// let mut table = SymbolTable::new();
+    /// let sc = Scanny::new("foo bar foo");
+    /// let mut syms = Vec::new();
+    /// while sc.peek().is_some() {
+    ///     sc.skeep_while(char::is_whitespace)
+    ///         .matcher()
+    ///         .consume_while(char::is_ascii_alphabetic);
+    ///     syms.push(sc.intern_finalize(&mut table).unwrap().value);
+    /// }
+    /// assert_eq!(syms[0], syms[2]);
+    /// assert_ne!(syms[0], syms[1]);
+    /// assert_eq!(table.resolve(syms[1]), "bar");
+    /// ```
+    pub fn intern_finalize(&self, table: &mut SymbolTable<'a>) -> Option<WithPos<Symbol>> {
+        let token = self.finalize(|v| v.is_matched().then(|| v.value()))?;
+        let span = token.span();
+        let char_pos = token.get_char_pos();
+        let sym = table.intern(token.value?);
+        Some(
+            WithPos::new(sym)
+                .set_byte_pos(span.get_byte_pos())
+                .set_line_pos(span.get_line_pos())
+                .set_char_pos(char_pos),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SymbolTable;
+    use crate::Scanny;
+
+    #[test]
+    fn test_intern_finalize() {
+        let mut table = SymbolTable::new();
+        let sc = Scanny::new("let x = x;");
+        sc.matcher().consume_while(char::is_ascii_alphabetic);
+        let kw = sc.intern_finalize(&mut table).unwrap();
+        assert_eq!(kw.get_byte_pos(), 0..3);
+        sc.skeep_while(char::is_whitespace)
+            .matcher()
+            .consume_while(char::is_ascii_alphabetic);
+        let x1 = sc.intern_finalize(&mut table).unwrap();
+        sc.skeep_while(|v| !v.is_ascii_alphabetic())
+            .matcher()
+            .consume_while(char::is_ascii_alphabetic);
+        let x2 = sc.intern_finalize(&mut table).unwrap();
+        assert_eq!(x1.value, x2.value);
+        assert_eq!(x2.get_byte_pos(), 8..9);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.get("x"), Some(x1.value));
+
+        sc.matcher().then('x');
+        assert_eq!(sc.intern_finalize(&mut table), None);
+        assert_eq!(sc.intern_finalize(&mut table), None);
+        assert_eq!(table.len(), 2);
+    }
+}
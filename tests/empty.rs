@@ -0,0 +1,143 @@
+use scanny::{CharClass, Scanny, Span};
+
+fn empty_token<T: std::fmt::Debug>(sc: &Scanny, token: Option<scanny::WithPos<T>>) {
+    let token = token.unwrap();
+    assert_eq!(token.get_byte_pos(), 0..0);
+    assert_eq!(token.get_line_pos(), 1..=1);
+    assert_eq!(token.get_char_pos(), 0..0);
+    assert!(sc.at_end());
+}
+
+#[test]
+fn test_empty_cursor() {
+    let sc = Scanny::new("");
+    assert!(sc.at_end());
+    assert_eq!(sc.peek(), None);
+    assert_eq!(sc.peek_second(), None);
+    assert_eq!(sc.peek_third(), None);
+    assert_eq!(sc.peek_nth(0), None);
+    assert_eq!(sc.peek_with_offset(0), None);
+    assert_eq!(sc.peek_until('x'), None);
+    assert_eq!(sc.peek_line(), "");
+    assert_eq!(sc.bump(), None);
+    assert_eq!(sc.bump_bytes(3), "");
+    assert_eq!(sc.match_one(), None);
+    assert!(!sc.opt_char('a'));
+    assert!(!sc.opt_str("a"));
+    sc.skip_bom().skip_hspace().skeep_while(|_| true);
+    let pos = sc.position();
+    assert_eq!((pos.byte, pos.line, pos.column), (0, 1, 1));
+    assert_eq!(sc.locate(0), Some(pos));
+    assert_eq!(sc.locate(1), None);
+    assert_eq!(&*sc.line_starts(), &[0]);
+    assert_eq!(sc.since(sc.mark()), "");
+    assert_eq!(sc.bytes_consumed(), 0);
+    assert_eq!(sc.chars_consumed(), 0);
+    assert_eq!(sc.remaining_bytes(), 0);
+    assert_eq!(sc.remaining_chars(), 0);
+    assert_eq!(sc.utf16_offset(0), 0);
+    assert!(!sc.did_advance_since(pos));
+    assert_eq!(sc.current_match_span(), None);
+    assert_eq!(sc.match_keyword_set(&["a"]), None);
+    assert_eq!(sc.parse_u64(10), None);
+    assert_eq!(sc.parse_i64(10), None);
+    assert_eq!(sc.match_unicode_escape(), None);
+    assert_eq!(sc.match_char_literal('\'', '\\'), None);
+    assert_eq!(sc.recover_to(&[';']).get_byte_pos(), 0..0);
+    assert!(
+        sc.collect_tokens(|sc| sc.matcher().finalize(|_| ()), 0)
+            .is_empty()
+    );
+    assert_eq!(
+        sc.try_tokens(|_| Some(Err::<scanny::WithPos<()>, _>(())))
+            .count(),
+        0
+    );
+    assert_eq!(sc.render_span(&Span::new(0..0, 1..=1)), "1 |\n  | ^");
+    assert_eq!(sc.spanned(pos, ()).get_byte_pos(), 0..0);
+    assert!(!sc.would_match(|sc| sc.then('a')));
+    assert!(sc.would_match(|sc| sc.consume_while(|_| true)));
+}
+
+#[test]
+fn test_empty_finalize() {
+    let sc = Scanny::new("");
+    assert_eq!(sc.finalize(|v| v.value()), None);
+    let token = sc.matcher().finalize(|v| (v.is_matched(), v.value()));
+    assert_eq!(token.as_ref().unwrap().value, (true, ""));
+    empty_token(&sc, token);
+}
+
+#[test]
+fn test_match_type_is_empty_at_eof() {
+    let sc = Scanny::new("ab");
+    let token = sc.matcher().then('a').then('b').finalize(|v| v.is_empty());
+    assert!(!token.unwrap().value);
+    let token = sc
+        .matcher()
+        .then('a')
+        .finalize(|v| (v.is_empty(), v.is_matched()));
+    assert_eq!(token.as_ref().unwrap().value, (true, false));
+    assert_eq!(token.unwrap().get_byte_pos(), 2..2);
+}
+
+#[test]
+fn test_empty_succeeding_combinators() {
+    let sc = Scanny::new("");
+    sc.matcher()
+        .then_optional('a')
+        .then_any_optional(&['a'])
+        .then_any_str_optional(&["ab"])
+        .match_char_optional(|_| true)
+        .then_repeat('a', 0)
+        .match_exact_run(|_| true, 0)
+        .consume_while(|_| true)
+        .consume_each(|_| std::ops::ControlFlow::Continue(()))
+        .consume_while_if(|_| true, |_| true)
+        .consume_class(&CharClass::new().ascii_digit())
+        .consume_until_any(&['a'])
+        .consume_until_any_str(&["a"])
+        .consume_while_in(&['a'..='z'])
+        .then_unless_str("a")
+        .then_if_str("")
+        .peek_and_consume(|sc| sc.peek().is_some())
+        .not(|sc| sc.peek().is_some())
+        .custom(|rest| Some(rest.len()));
+    assert!(sc.match_succeeded());
+    let token = sc.finalize(|v| v.is_matched());
+    assert!(token.as_ref().unwrap().value);
+    empty_token(&sc, token);
+}
+
+#[test]
+fn test_empty_failing_combinators() {
+    type Chain = fn(&Scanny) -> bool;
+    let chains: [Chain; 17] = [
+        |sc| sc.then('a').match_failed(),
+        |sc| sc.match_char(|_| true).match_failed(),
+        |sc| sc.then_repeat('a', 1).match_failed(),
+        |sc| sc.match_exact_run(|_| true, 1).match_failed(),
+        |sc| sc.then_range('a'..='z').match_failed(),
+        |sc| sc.then_ranges(&['a'..='z']).match_failed(),
+        |sc| {
+            sc.match_class(&CharClass::new().ascii_alpha())
+                .match_failed()
+        },
+        |sc| sc.then_any(|v| v.is_some()).match_failed(),
+        |sc| sc.then_while(|_| true).match_failed(),
+        |sc| sc.match_hspace().match_failed(),
+        |sc| sc.then_if_str("a").match_failed(),
+        |sc| sc.match_prefixed_int().match_failed(),
+        |sc| sc.match_float(true).match_failed(),
+        |sc| sc.match_quoted_doubled('\'').match_failed(),
+        |sc| sc.then_peek(|sc| sc.peek().is_some()).match_failed(),
+        |sc| sc.custom(|_| None).match_failed(),
+        |sc| sc.then_map(Some).is_none() && sc.match_succeeded(),
+    ];
+    for (i, chain) in chains.iter().enumerate() {
+        let sc = Scanny::new("");
+        assert!(chain(sc.matcher()), "chain {i}");
+        let token = sc.finalize(|_| ());
+        empty_token(&sc, token);
+    }
+}
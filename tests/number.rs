@@ -136,3 +136,156 @@ fn test_match_float_2() {
         ],
     );
 }
+
+fn get_prefixed_int<'a>(sc: &'a Scanny<'a>) -> MatchType<'a> {
+    sc.skeep_while(char::is_whitespace)
+        .matcher()
+        .match_prefixed_int()
+        .finalize(|v| v)
+        .unwrap()
+        .value
+}
+
+#[test]
+fn test_prefixed_int() {
+    let sc = Scanny::new("0x1F 0XdEaD_bEeF 0o777 0O1_0 0b1010 0B_1 1_000 0 07");
+    for expected in [
+        "0x1F",
+        "0XdEaD_bEeF",
+        "0o777",
+        "0O1_0",
+        "0b1010",
+        "0B_1",
+        "1_000",
+        "0",
+        "07",
+    ] {
+        let matched = get_prefixed_int(&sc);
+        assert!(matched.is_matched());
+        assert_eq!(matched.value(), expected);
+    }
+    assert_eq!(sc.peek(), None);
+
+    let sc = Scanny::new("0b12 0o8");
+    let matched = get_prefixed_int(&sc);
+    assert!(matched.is_matched());
+    assert_eq!(matched.value(), "0b1");
+    assert_eq!(sc.peek(), Some('2'));
+    sc.bump();
+    let matched = get_prefixed_int(&sc);
+    assert!(matched.is_not_matched());
+    assert_eq!(matched.value(), "0o");
+}
+
+#[test]
+fn test_prefixed_int_without_digits() {
+    for src in ["0x ", "0o", "0b_", "_1", "x1"] {
+        let sc = Scanny::new(src);
+        let matched = get_prefixed_int(&sc);
+        assert!(matched.is_not_matched(), "{src}");
+    }
+}
+
+fn match_float(src: &str, exponent: bool) -> (bool, &str) {
+    let sc = Scanny::new(src);
+    sc.matcher()
+        .match_float(exponent)
+        .finalize(|v| (v.is_matched(), v.value()))
+        .unwrap()
+        .value
+}
+
+#[test]
+fn test_match_float_exponent() {
+    assert_eq!(match_float("1e5", true), (true, "1e5"));
+    assert_eq!(match_float("1.2e-3", true), (true, "1.2e-3"));
+    assert_eq!(match_float("1.e+4", true), (true, "1.e+4"));
+    assert_eq!(match_float("2E10;", true), (true, "2E10"));
+    assert_eq!(match_float("1_000.5_5e1_0", true), (true, "1_000.5_5e1_0"));
+    assert_eq!(match_float("12.5", true), (true, "12.5"));
+    assert_eq!(match_float("12.", true), (true, "12."));
+    assert_eq!(match_float("1e", true), (false, "1e"));
+    assert_eq!(match_float("1.5e+", true), (false, "1.5e+"));
+    assert_eq!(match_float("12", true), (false, "12"));
+    assert_eq!(match_float(".5", true), (false, ""));
+
+    assert_eq!(match_float("1.5e10", false), (true, "1.5"));
+    assert_eq!(match_float("1e5", false), (false, "1"));
+}
+
+#[test]
+fn test_parse_int() {
+    let sc = Scanny::new("12_3 +7 -x 18446744073709551615 18446744073709551616");
+    let num = sc.parse_u64(10).unwrap();
+    assert_eq!((num.value, num.get_byte_pos()), (123, 0..4));
+    sc.bump();
+    assert_eq!(sc.parse_u64(10), None);
+    let num = sc.parse_i64(10).unwrap();
+    assert_eq!((num.value, num.get_byte_pos()), (7, 5..7));
+    sc.bump();
+    assert_eq!(sc.parse_i64(10), None);
+    assert_eq!(sc.peek(), Some('-'));
+    sc.bump_bytes(3);
+    assert_eq!(sc.parse_u64(10).unwrap().value, u64::MAX);
+    sc.bump();
+    assert_eq!(sc.parse_u64(10), None);
+    assert_eq!(sc.parse_i64(10), None);
+    assert_eq!(sc.peek(), Some('1'));
+    assert_eq!(Scanny::new("_1").parse_u64(10), None);
+    assert_eq!(Scanny::new("9223372036854775808").parse_i64(10), None);
+    assert_eq!(Scanny::new("-1_0").parse_i64(2).unwrap().value, -2);
+}
+
+#[test]
+fn test_int_strict() {
+    let int = |src: &str, radix| {
+        let sc = Scanny::new(src);
+        let token = sc
+            .matcher()
+            .match_int_strict(radix)
+            .finalize(|v| (v.is_matched(), v.value().to_string()))
+            .unwrap();
+        token.value
+    };
+    assert_eq!(int("1_2_3;", 10), (true, "1_2_3".to_string()));
+    assert_eq!(int("7", 10), (true, "7".to_string()));
+    assert_eq!(int("ff_ff", 16), (true, "ff_ff".to_string()));
+    assert_eq!(int("12_", 10), (false, "12_".to_string()));
+    assert_eq!(int("1__2", 10), (false, "1_".to_string()));
+    assert_eq!(int("_1", 10), (false, "".to_string()));
+    assert_eq!(int("1_a", 10), (false, "1_".to_string()));
+    assert_eq!(int("", 10), (false, "".to_string()));
+}
+
+fn match_float_with(src: &str, decimal_point: char, grouping: char) -> (bool, &str) {
+    let sc = Scanny::builder(src)
+        .number_format(decimal_point, grouping)
+        .build();
+    sc.matcher()
+        .match_float(true)
+        .finalize(|v| (v.is_matched(), v.value()))
+        .unwrap()
+        .value
+}
+
+#[test]
+fn test_match_float_number_format() {
+    assert_eq!(match_float_with("1,234.5", '.', ','), (true, "1,234.5"));
+    assert_eq!(match_float_with("3.14,", '.', ','), (true, "3.14"));
+    assert_eq!(match_float_with("3,14", '.', ','), (false, "3,14"));
+    assert_eq!(match_float_with("1_0.5", '.', ','), (false, "1"));
+
+    assert_eq!(match_float_with("1.234,5", ',', '.'), (true, "1.234,5"));
+    assert_eq!(match_float_with("3,14.", ',', '.'), (true, "3,14"));
+    assert_eq!(match_float_with("2,5e3", ',', '.'), (true, "2,5e3"));
+    assert_eq!(match_float_with("3.14", ',', '.'), (false, "3.14"));
+    assert_eq!(match_float_with("12,", ',', '.'), (true, "12,"));
+
+    assert_eq!(match_float("1_.5", false), (false, "1"));
+}
+
+#[test]
+#[should_panic(expected = "must differ")]
+fn test_number_format_same_chars() {
+    Scanny::new("1").set_number_format('.', '.');
+}
@@ -0,0 +1,99 @@
+use proptest::prelude::*;
+use scanny::Scanny;
+
+#[derive(Debug, Clone)]
+enum Op {
+    Bump,
+    BumpBytes(usize),
+    SkeepWhitespace,
+    ConsumeWhile(char),
+    Matcher,
+    Then(char),
+    Finalize { consume: bool },
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        Just(Op::Bump),
+        (0..8usize).prop_map(Op::BumpBytes),
+        Just(Op::SkeepWhitespace),
+        any::<char>().prop_map(Op::ConsumeWhile),
+        Just(Op::Matcher),
+        any::<char>().prop_map(Op::Then),
+        any::<bool>().prop_map(|consume| Op::Finalize { consume }),
+    ]
+}
+
+fn input() -> impl Strategy<Value = String> {
+    prop_oneof![any::<String>(), "[a\n\r é€😀\t]{0,40}",]
+}
+
+fn apply(src: &str, sc: &Scanny, op: &Op) {
+    match *op {
+        Op::Bump => {
+            sc.bump();
+        }
+        Op::BumpBytes(n) => {
+            let rest = &src[sc.position().byte..];
+            let n = (0..=n.min(rest.len()))
+                .rev()
+                .find(|v| rest.is_char_boundary(*v))
+                .unwrap();
+            sc.bump_bytes(n);
+        }
+        Op::SkeepWhitespace => {
+            sc.skeep_while(char::is_whitespace);
+        }
+        Op::ConsumeWhile(ch) => {
+            sc.consume_while(|v| *v != ch);
+        }
+        Op::Matcher => {
+            sc.matcher();
+        }
+        Op::Then(ch) => {
+            sc.then(ch);
+        }
+        Op::Finalize { consume } => {
+            sc.finalize(|v| {
+                v.consume_on_match(consume);
+                v.consume_on_not_match(consume);
+            });
+        }
+    }
+}
+
+fn check(src: &str, sc: &Scanny) {
+    let pos = sc.position();
+    assert!(src.is_char_boundary(pos.byte));
+    let consumed = &src[..pos.byte];
+    assert_eq!(sc.bytes_consumed(), consumed.len());
+    assert_eq!(sc.chars_consumed(), consumed.chars().count());
+    assert_eq!(pos.line, 1 + consumed.matches('\n').count());
+    assert_eq!(sc.peek(), src[pos.byte..].chars().next());
+}
+
+proptest! {
+    #[test]
+    fn positions_follow_consumed_input(src in input(), ops in prop::collection::vec(op(), 0..32)) {
+        let sc = Scanny::new(&src);
+        for op in &ops {
+            apply(&src, &sc, op);
+            check(&src, &sc);
+        }
+        sc.finalize(|_| ());
+        check(&src, &sc);
+    }
+
+    #[test]
+    fn bump_to_end_counts_every_char(src in input()) {
+        let sc = Scanny::new(&src);
+        let mut bumped = String::new();
+        while let Some(ch) = sc.bump() {
+            bumped.push(ch);
+            check(&src, &sc);
+        }
+        prop_assert_eq!(bumped, src.clone());
+        prop_assert_eq!(sc.bytes_consumed(), src.len());
+        prop_assert!(sc.at_end());
+    }
+}